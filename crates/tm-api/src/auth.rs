@@ -0,0 +1,109 @@
+use crate::client::ApiClient;
+use crate::ApiError::{LoginError, WebRequestError};
+use anyhow::{Context, Result};
+use reqwest::header::SET_COOKIE;
+use reqwest::StatusCode;
+use std::fmt;
+use tm_macros::forum_url;
+use tracing::debug;
+
+/// Session of a logged-in user.
+///
+/// Pass to [ApiClient::with_session] so that requests are sent as the user.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SessionToken {
+    /// Cookie string in `name1=value1; name2=value2` format, ready to use as `Cookie` header.
+    pub cookie: String,
+}
+
+// Cookie is the credential of user, never print it in logs.
+impl fmt::Debug for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionToken")
+            .field("cookie", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Login with `username` and `password`, return the session.
+///
+/// Use [ApiClient::login] instead if the client is used in following requests.
+pub async fn login(username: &str, password: &str) -> Result<SessionToken> {
+    ApiClient::new().login(username, password).await
+}
+
+impl ApiClient {
+    /// Login with `username` and `password`, return the session.
+    ///
+    /// The returned session is not applied on current client, use [ApiClient::with_session] to
+    /// build a logged-in client.
+    #[tracing::instrument(skip(self, password))]
+    pub async fn login(&self, username: &str, password: &str) -> Result<SessionToken> {
+        let target = forum_url!(
+            base = self.config.base_url,
+            "member.php",
+            mod = "logging",
+            action = "login",
+            loginsubmit = "yes",
+            inajax = 1
+        );
+        debug!("login on url {target}");
+        let resp = self
+            .client
+            .post(target.as_str())
+            .form(&[
+                ("username", username),
+                ("password", password),
+                ("loginfield", "username"),
+                ("questionid", "0"),
+                ("answer", ""),
+                ("cookietime", "2592000"),
+            ])
+            .send()
+            .await
+            .context("failed to send login request")?;
+        if resp.status() != StatusCode::OK {
+            debug!("bad response status: {}", resp.status());
+            return Err(WebRequestError(target, resp.status().to_string()).into());
+        }
+
+        // Only keep the "name=value" part, attributes like path and expires are not needed when
+        // sending back.
+        let cookies = resp
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|x| x.to_str().ok())
+            .filter_map(|x| x.split(';').next())
+            .map(|x| x.trim().to_string())
+            .collect::<Vec<_>>();
+
+        // Server sets the auth cookie only when login succeeded.
+        if !cookies.iter().any(|x| {
+            x.split('=')
+                .next()
+                .is_some_and(|name| name.ends_with("_auth"))
+        }) {
+            return Err(LoginError(username.to_string()).into());
+        }
+
+        Ok(SessionToken {
+            cookie: cookies.join("; "),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts_cookie() {
+        let session = SessionToken {
+            cookie: "s_auth=secret".to_string(),
+        };
+        assert!(!format!("{session:?}").contains("secret"));
+        let client = ApiClient::new().with_session(session);
+        assert!(!format!("{client:?}").contains("secret"));
+    }
+}
@@ -0,0 +1,206 @@
+use crate::auth::SessionToken;
+use crate::rate_limit::RateLimiter;
+use crate::utils::{build_client, http_get, http_get_with_retry, USER_AGENT};
+use reqwest::{Response, Url};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tm_types::BASE_URL;
+
+/// Settings used to build an [ApiClient].
+///
+/// Start from the default value and override fields in need:
+///
+/// ```
+/// use std::time::Duration;
+/// use tm_api::{ApiClient, ApiConfig};
+///
+/// let client = ApiClient::with_config(ApiConfig {
+///     base_url: "http://127.0.0.1:8080".to_string(),
+///     timeout: Some(Duration::from_secs(10)),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct ApiConfig {
+    /// Base url of the forum, without trailing slash.
+    pub base_url: String,
+
+    /// User agent in request headers.
+    pub user_agent: String,
+
+    /// Timeout for each request.
+    ///
+    /// `None` means no timeout.
+    pub timeout: Option<Duration>,
+
+    /// Max count of retries when request failed on network errors or 5xx responses.
+    ///
+    /// `0` disables retry.
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubles on each following retry.
+    pub retry_delay: Duration,
+
+    /// Upper bound of the delay between retries.
+    ///
+    /// Set to the same value as `retry_delay` to retry with a fixed delay.
+    pub max_retry_delay: Duration,
+
+    /// Max count of requests running at the same time when fetching in batch, like
+    /// [ApiClient::fetch_thread_all_pages].
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            user_agent: USER_AGENT.to_string(),
+            timeout: None,
+            max_retries: 0,
+            retry_delay: Duration::from_secs(1),
+            max_retry_delay: Duration::from_secs(30),
+            max_concurrent_requests: 4,
+        }
+    }
+}
+
+/// Client to access forum APIs.
+///
+/// Holds a single [reqwest::Client] so that connections are pooled and reused across requests,
+/// prefer this over free functions like [crate::thread::fetch_thread_content] when sending a
+/// batch of requests.
+#[derive(Clone)]
+pub struct ApiClient {
+    /// Underlying http client.
+    pub(crate) client: reqwest::Client,
+
+    /// Settings the client built with.
+    pub(crate) config: ApiConfig,
+
+    /// Optional limiter applied on every request.
+    ///
+    /// Shared between clones of the client.
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+
+    /// Optional user session sent along with every request.
+    pub(crate) session: Option<SessionToken>,
+}
+
+// Written by hand to make sure the session cookie is never printed, see [SessionToken].
+impl fmt::Debug for ApiClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiClient")
+            .field("client", &self.client)
+            .field("config", &self.config)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("session", &self.session)
+            .finish()
+    }
+}
+
+impl ApiClient {
+    /// Build a new client with default settings.
+    pub fn new() -> Self {
+        Self::with_config(ApiConfig::default())
+    }
+
+    /// Build a new client with given settings.
+    pub fn with_config(config: ApiConfig) -> Self {
+        Self {
+            client: build_client(&config),
+            config,
+            rate_limiter: None,
+            session: None,
+        }
+    }
+
+    /// Send all requests as the user logged in `session`.
+    pub fn with_session(mut self, session: SessionToken) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Limit the rate of all requests sent by the client with `rate_limiter`.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(Arc::new(rate_limiter));
+        self
+    }
+
+    /// Settings the client built with.
+    pub fn config(&self) -> &ApiConfig {
+        &self.config
+    }
+
+    /// Send a GET request to `url`, retry as configured.
+    ///
+    /// Carries the session cookie if logged in and `url` is on the forum host.
+    pub(crate) async fn get(&self, url: &str) -> reqwest::Result<Response> {
+        let session = self.session_for(url);
+        match self.config.max_retries {
+            0 => {
+                if let Some(rate_limiter) = self.rate_limiter.as_ref() {
+                    rate_limiter.wait().await;
+                }
+                http_get(&self.client, url, session).await
+            }
+            n => {
+                http_get_with_retry(
+                    &self.client,
+                    url,
+                    session,
+                    self.rate_limiter.as_deref(),
+                    n.saturating_add(1),
+                    self.config.retry_delay,
+                    self.config.max_retry_delay,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Session to send along with request to `url`.
+    ///
+    /// Only urls on the same host and port as `base_url` get the session, other hosts like the
+    /// ones serving avatars shall never see the cookie.
+    fn session_for(&self, url: &str) -> Option<&SessionToken> {
+        let session = self.session.as_ref()?;
+        let base_url = Url::parse(self.config.base_url.as_str()).ok()?;
+        let url = Url::parse(url).ok()?;
+        let same_origin = base_url.host_str().is_some()
+            && url.host_str() == base_url.host_str()
+            && url.port_or_known_default() == base_url.port_or_known_default();
+        same_origin.then_some(session)
+    }
+}
+
+impl Default for ApiClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_only_sent_to_forum_host() {
+        let client = ApiClient::new().with_session(SessionToken {
+            cookie: "s_auth=secret".to_string(),
+        });
+        assert!(client
+            .session_for(format!("{BASE_URL}/forum.php?mod=viewthread").as_str())
+            .is_some());
+        assert!(client.session_for("https://evil.example/x.png").is_none());
+        assert!(client
+            .session_for("https://www.tsdm39.com.evil.example/x.png")
+            .is_none());
+        assert!(client
+            .session_for("https://www.tsdm39.com:8443/x")
+            .is_none());
+        assert!(client.session_for("not a url").is_none());
+        assert!(ApiClient::new().session_for(BASE_URL).is_none());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::client::ApiClient;
+use crate::decompress_response_to_string;
+use crate::ApiError::WebRequestError;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use reqwest::StatusCode;
+use select::document::Document;
+use select::node::Node;
+use select::predicate::{Class, Name, Predicate};
+use serde::{Deserialize, Serialize};
+use tm_html::HtmlElementExt;
+use tm_macros::forum_url;
+use tracing::{debug, trace};
+
+/// Summary of a subforum listed in forum index page.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ForumSummary {
+    /// Forum id.
+    pub id: String,
+
+    /// Forum name.
+    pub name: String,
+
+    /// Optional forum description.
+    pub description: Option<String>,
+
+    /// Count of threads in forum, if shown.
+    pub thread_count: Option<u32>,
+}
+
+/// Fetch all subforums listed in forum index page.
+///
+/// Use [ApiClient::fetch_forum_list] instead when sending other requests.
+pub async fn fetch_forum_list() -> Result<Vec<ForumSummary>> {
+    ApiClient::new().fetch_forum_list().await
+}
+
+impl ApiClient {
+    /// Fetch all subforums listed in forum index page.
+    pub async fn fetch_forum_list(&self) -> Result<Vec<ForumSummary>> {
+        let target = forum_url!(base = self.config.base_url, "forum.php");
+        debug!("fetch forum list on url {target}");
+        let resp = self
+            .get(target.as_str())
+            .await
+            .context("failed to get forum list")?;
+        if resp.status() != StatusCode::OK {
+            debug!("bad response status: {}", resp.status());
+            return Err(WebRequestError(target, resp.status().to_string()).into());
+        }
+        let data = decompress_response_to_string(resp)
+            .await
+            .context("when parsing forum list data")?;
+
+        trace!("document: {data:?}");
+
+        parse_forum_list(data)
+    }
+}
+
+/// Parse subforums in forum index page `html`.
+///
+/// Both layouts are supported:
+///
+/// * Forums in columns, each forum in a `<td class="fl_g">`.
+/// * Forums in rows, each forum in a `<tr>` with name in `<h2>`.
+pub fn parse_forum_list(html: impl AsRef<str>) -> Result<Vec<ForumSummary>> {
+    let doc = Document::from(html.as_ref());
+    let fid_re = Regex::new(r#"fid=(?<fid>\d+)"#).expect("invalid fid regex");
+
+    let mut forums = doc
+        .find(Name("td").and(Class("fl_g")))
+        .filter_map(|x| parse_column_forum(x, &fid_re))
+        .collect::<Vec<_>>();
+    forums.extend(
+        doc.find(Name("tr"))
+            .filter_map(|x| parse_row_forum(x, &fid_re)),
+    );
+
+    if forums.is_empty() {
+        bail!("forum list not found");
+    }
+
+    Ok(forums)
+}
+
+/// Parse forum in column layout.
+///
+/// ```html
+/// <td class="fl_g">
+///   <dl>
+///     <dt><a href="forum.php?mod=forumdisplay&fid=8">name</a></dt>
+///     <dd class="xg2">description</dd>
+///     <dd><em>主题: <span title="12345">1万</span></em>, <em>帖数: 67890</em></dd>
+///   </dl>
+/// </td>
+/// ```
+fn parse_column_forum(node: Node, fid_re: &Regex) -> Option<ForumSummary> {
+    let (id, name) = parse_forum_link(node.find(Name("dt").child(Name("a"))).next()?, fid_re)?;
+    let description = node
+        .find(Name("dd").and(Class("xg2")))
+        .next()
+        .map(|x| x.text().trim().to_string())
+        .filter(|x| !x.is_empty());
+    let thread_count = node
+        .find(Name("dd").child(Name("em")))
+        .find(|x| x.text().contains("主题"))
+        .and_then(|x| {
+            // Large numbers are abbreviated in text, the exact value is in title.
+            x.find(Name("span"))
+                .next()
+                .and_then(|x| x.attr("title"))
+                .and_then(parse_count)
+                .or_else(|| parse_count(x.text().as_str()))
+        });
+
+    Some(ForumSummary {
+        id,
+        name,
+        description,
+        thread_count,
+    })
+}
+
+/// Parse forum in row layout.
+///
+/// ```html
+/// <tr>
+///   <td>
+///     <h2><a href="forum.php?mod=forumdisplay&fid=8">name</a></h2>
+///     <p class="xg2">description</p>
+///   </td>
+///   <td class="fl_i"><span class="xi2">12345</span><span class="xg1"> / 67890</span></td>
+/// </tr>
+/// ```
+fn parse_row_forum(node: Node, fid_re: &Regex) -> Option<ForumSummary> {
+    let (id, name) = parse_forum_link(node.find(Name("h2").child(Name("a"))).next()?, fid_re)?;
+    let description = node
+        .find(Name("p").and(Class("xg2")))
+        .next()
+        .map(|x| x.text().trim().to_string())
+        .filter(|x| !x.is_empty());
+    let thread_count = node
+        .find(Class("fl_i").descendant(Name("span").and(Class("xi2"))))
+        .next()
+        .and_then(|x| parse_count(x.text().as_str()));
+
+    Some(ForumSummary {
+        id,
+        name,
+        description,
+        thread_count,
+    })
+}
+
+/// Parse forum id and name from the forum link `<a>`.
+fn parse_forum_link(node: Node, fid_re: &Regex) -> Option<(String, String)> {
+    let id = fid_re
+        .captures(node.attr("href")?)?
+        .name("fid")?
+        .as_str()
+        .to_string();
+    let name = node.first_end_deep_text().trim().to_string();
+    if name.is_empty() {
+        return None;
+    }
+    Some((id, name))
+}
+
+/// Parse count in text like "主题: 12345".
+fn parse_count(text: &str) -> Option<u32> {
+    text.chars()
+        .filter(|x| x.is_ascii_digit())
+        .collect::<String>()
+        .parse::<u32>()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_forum_list() {
+        let html = r#"<html><body><table>
+<tr><td class="fl_g"><dl>
+<dt><a href="forum.php?mod=forumdisplay&amp;fid=8">Anime</a></dt>
+<dd class="xg2">Anime talks</dd>
+<dd><em>主题: <span title="12345">1万</span></em>, <em>帖数: 67890</em></dd>
+</dl></td></tr>
+<tr><td><h2><a href="forum.php?mod=forumdisplay&amp;fid=9">Music</a></h2></td>
+<td class="fl_i"><span class="xi2">42</span><span class="xg1"> / 100</span></td></tr>
+</table></body></html>"#;
+        let forums = parse_forum_list(html).unwrap();
+        assert_eq!(forums.len(), 2);
+        assert_eq!(forums[0].id, "8");
+        assert_eq!(forums[0].name, "Anime");
+        assert_eq!(forums[0].description.as_deref(), Some("Anime talks"));
+        assert_eq!(forums[0].thread_count, Some(12345));
+        assert_eq!(forums[1].id, "9");
+        assert_eq!(forums[1].name, "Music");
+        assert_eq!(forums[1].description, None);
+        assert_eq!(forums[1].thread_count, Some(42));
+
+        assert!(parse_forum_list("<html></html>").is_err());
+    }
+}
@@ -6,8 +6,16 @@ use serde::{Deserialize, Serialize};
 use std::io::Read;
 use std::num::NonZeroI32;
 
+pub use auth::{login, SessionToken};
+pub use client::{ApiClient, ApiConfig};
+pub use rate_limit::RateLimiter;
+
+pub mod auth;
+pub mod client;
+pub mod forum;
 pub mod post;
 pub mod profile;
+pub mod rate_limit;
 pub mod thread;
 mod utils;
 
@@ -28,6 +36,12 @@ pub enum ApiError {
     /// * 1: `message` the error message in reply.
     #[error("server responded an error: status={0:?}, message={1:?}")]
     ServerRespError(NonZeroI32, String),
+
+    /// Login failed, server did not set the auth cookie.
+    ///
+    /// * 0: username.
+    #[error("failed to login as user {0:?}")]
+    LoginError(String),
 }
 
 /// Represents the common format of the response when server replied an error>
@@ -68,6 +82,20 @@ pub(crate) async fn decompress_response_to_string(resp: Response) -> Result<Stri
                 d.read_to_string(&mut s)?;
                 s
             }
+            "br" => {
+                let b = resp.bytes().await?;
+                let mut d = brotli::Decompressor::new(b.iter().as_slice(), 4096);
+                let mut s = String::new();
+                d.read_to_string(&mut s)?;
+                s
+            }
+            "zstd" => {
+                let b = resp.bytes().await?;
+                let mut d = zstd::stream::read::Decoder::new(b.iter().as_slice())?;
+                let mut s = String::new();
+                d.read_to_string(&mut s)?;
+                s
+            }
             v => bail!(format!("unsupported http response encoding format: {}", v)),
         },
         None => resp.text().await?,
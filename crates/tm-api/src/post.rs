@@ -1,5 +1,7 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use tm_types::PlatformValue;
+use tm_macros::forum_url;
+use tm_types::{PlatformValue, PostId, UserId};
 
 /// Post in thread.
 ///
@@ -15,14 +17,14 @@ use tm_types::PlatformValue;
 pub struct Post {
     /// Post id.
     #[serde(rename = "pid")]
-    pub id: String,
+    pub id: PostId,
 
     /// Author name.
     pub author: String,
 
     /// Uid of `author`.
     #[serde(rename = "authorid")]
-    pub author_id: String,
+    pub author_id: UserId,
 
     /// Author avatar url.
     pub avatar: String,
@@ -73,7 +75,30 @@ pub struct Post {
     pub platform: PlatformValue,
 }
 
+impl Post {
+    /// Check if the post is the first floor in thread.
+    pub fn is_first_floor(&self) -> bool {
+        self.first_floor == "1"
+    }
+
+    /// Parse `timestamp` into UTC date time.
+    ///
+    /// Return `None` if the timestamp is invalid.
+    pub fn timestamp_as_datetime(&self) -> Option<DateTime<Utc>> {
+        let secs = self.timestamp.trim().parse::<i64>().ok()?;
+        DateTime::from_timestamp(secs, 0)
+    }
+}
+
 /// Generate a find post link for post specified by post id `pid`.
+///
+/// The link is relative to the forum root, like `forum.php?mod=redirect&goto=findpost&pid=1`.
 pub fn generate_find_post_link(pid: impl AsRef<str>) -> String {
-    format!("forum.php?mod=redirect&goto=findpost&pid={}", pid.as_ref())
+    forum_url!(
+        base = "",
+        "forum.php",
+        mod = "redirect",
+        goto = "findpost",
+        pid = pid.as_ref()
+    )
 }
@@ -1,5 +1,5 @@
+use crate::client::ApiClient;
 use crate::decompress_response_to_string;
-use crate::utils::http_get;
 use crate::ApiError::WebRequestError;
 use anyhow::{bail, Context, Result};
 use racros::AutoDebug;
@@ -10,7 +10,7 @@ use select::predicate::{Attr, Class, Name, Predicate};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tm_html::HtmlElementExt;
-use tm_types::BASE_URL;
+use tm_macros::forum_url;
 use tracing::{debug, trace};
 
 /// User profile model for plain web page implementation.
@@ -174,47 +174,72 @@ pub struct Profile {
 }
 
 /// Fetch user profile info by user id.
+///
+/// Use [ApiClient::fetch_user_profile_by_id] instead when fetching multiple profiles.
 pub async fn fetch_user_profile_by_id(uid: impl AsRef<str>) -> Result<Profile> {
-    let target = format!("{}/home.php?mod=space&uid={}", BASE_URL, uid.as_ref());
-    debug!("fetch user profile (by uid) on url {target}");
-    let resp = http_get(target.as_str())
-        .await
-        .context("failed to get user profile by id")?;
-    if resp.status() != StatusCode::OK {
-        debug!("bad response status: {}", resp.status());
-        return Err(WebRequestError(target, resp.status().to_string()).into());
-    }
-    let data = decompress_response_to_string(resp)
-        .await
-        .context("when parsing user profile data")?;
-
-    trace!("document: {data:?}");
-
-    parse_profile_data(data)
+    ApiClient::new().fetch_user_profile_by_id(uid).await
 }
 
 /// Fetch user profile by username.
+///
+/// Use [ApiClient::fetch_user_profile_by_name] instead when fetching multiple profiles.
 pub async fn fetch_user_profile_by_name(username: impl AsRef<str>) -> Result<Profile> {
-    let target = format!(
-        "{}/home.php?mod=space&username={}",
-        BASE_URL,
-        username.as_ref()
-    );
-    debug!("fetch user profile (by name) on url {target}");
-    let resp = http_get(target.as_str())
-        .await
-        .context("failed to get user profile by name")?;
-    if resp.status() != StatusCode::OK {
-        debug!("bad response status: {}", resp.status());
-        return Err(WebRequestError(target, resp.status().to_string()).into());
+    ApiClient::new().fetch_user_profile_by_name(username).await
+}
+
+impl ApiClient {
+    /// Fetch user profile info by user id.
+    pub async fn fetch_user_profile_by_id(&self, uid: impl AsRef<str>) -> Result<Profile> {
+        let target = forum_url!(
+            base = self.config.base_url,
+            "home.php",
+            mod = "space",
+            uid = uid.as_ref()
+        );
+        debug!("fetch user profile (by uid) on url {target}");
+        self.fetch_user_profile(target, "failed to get user profile by id")
+            .await
     }
-    let data = decompress_response_to_string(resp)
-        .await
-        .context("when parsing user profile data")?;
 
-    trace!("document: {data:?}");
+    /// Fetch user profile by username.
+    pub async fn fetch_user_profile_by_name(&self, username: impl AsRef<str>) -> Result<Profile> {
+        let target = forum_url!(
+            base = self.config.base_url,
+            "home.php",
+            mod = "space",
+            username = username.as_ref()
+        );
+        debug!("fetch user profile (by name) on url {target}");
+        self.fetch_user_profile(target, "failed to get user profile by name")
+            .await
+    }
+
+    /// Fetch the avatar image on `url`, usually [Profile::avatar], return raw image bytes.
+    pub async fn fetch_avatar(&self, url: &str) -> Result<Vec<u8>> {
+        debug!("fetch avatar on url {url}");
+        let resp = self.get(url).await.context("failed to get avatar")?;
+        if resp.status() != StatusCode::OK {
+            debug!("bad response status: {}", resp.status());
+            return Err(WebRequestError(url.to_string(), resp.status().to_string()).into());
+        }
+        let data = resp.bytes().await.context("when reading avatar data")?;
+        Ok(data.to_vec())
+    }
 
-    parse_profile_data(data)
+    async fn fetch_user_profile(&self, target: String, context: &'static str) -> Result<Profile> {
+        let resp = self.get(target.as_str()).await.context(context)?;
+        if resp.status() != StatusCode::OK {
+            debug!("bad response status: {}", resp.status());
+            return Err(WebRequestError(target, resp.status().to_string()).into());
+        }
+        let data = decompress_response_to_string(resp)
+            .await
+            .context("when parsing user profile data")?;
+
+        trace!("document: {data:?}");
+
+        parse_profile_data(data)
+    }
 }
 
 pub fn parse_profile_data<'a>(html: impl AsRef<str>) -> Result<Profile> {
@@ -343,53 +368,39 @@ pub fn parse_profile_data<'a>(html: impl AsRef<str>) -> Result<Profile> {
                 .and(Class("c")),
         )
         .next();
-    let mut check_in_iter = check_in_node.and_then(|x| Some(x.find(Name("p"))));
+    // Direct `<p>` children of the check in node, the first child is the title.
+    let check_in_p = |n: usize| check_in_node.and_then(|x| x.nth_child_element(n));
     // p:nth-child(2)
-    let check_in_days_count = check_in_iter.as_mut().and_then(|x| {
-        x.next()
-            .and_then(|x| x.first_end_deep_text().parse::<usize>().ok())
-    });
+    let check_in_days_count =
+        check_in_p(1).and_then(|x| x.first_end_deep_text().parse::<usize>().ok());
     // p:nth-child(3)
-    let check_in_this_month_count = check_in_iter
-        .as_mut()
-        .and_then(|x| x.next())
-        .and_then(|x| Some(x.first_end_deep_text()));
+    let check_in_this_month_count = check_in_p(2).map(|x| x.first_end_deep_text());
     // p:nth-child(4)
-    let check_in_recent_time = check_in_iter
-        .as_mut()
-        .and_then(|x| x.next())
-        .and_then(|x| Some(x.first_end_deep_text()));
+    let check_in_recent_time = check_in_p(3).map(|x| x.first_end_deep_text());
     // p:nth-child(5) font:nth-child(1)
-    let mut child5 = check_in_iter.as_mut().and_then(|x| x.next());
+    let child5 = check_in_p(4);
     let check_in_all_coins = child5
         .and_then(|x| x.find(Name("font")).next())
-        .and_then(|x| Some(x.first_end_deep_text()));
+        .map(|x| x.first_end_deep_text());
     // p:nth-child(5) font:nth-child(2)
     let check_in_last_time_coins = child5
-        .as_mut()
-        .and_then(|x| x.next())
-        .and_then(|y| y.find(Name("font")).skip(1).next())
-        .and_then(|x| Some(x.first_end_deep_text()));
+        .and_then(|x| x.find(Name("font")).nth(1))
+        .map(|x| x.first_end_deep_text());
     // p:nth-child(6) font:nth-child(1)
-    let mut child6 = check_in_iter.as_mut().and_then(|x| x.next());
+    let child6 = check_in_p(5);
     let check_in_level = child6
         .and_then(|x| x.find(Name("font")).next())
-        .and_then(|x| Some(x.first_end_deep_text()));
+        .map(|x| x.first_end_deep_text());
     // p:nth-child(6) font:nth-child(2)
     let check_in_next_level = child6
-        .as_mut()
-        .and_then(|x| x.find(Name("font")).skip(1).next())
-        .and_then(|x| Some(x.first_end_deep_text()));
+        .and_then(|x| x.find(Name("font")).nth(1))
+        .map(|x| x.first_end_deep_text());
     // p:nth-child(6) font:nth-child(3)
     let check_in_next_level_days = child6
-        .as_mut()
-        .and_then(|x| x.find(Name("font")).skip(2).next())
-        .and_then(|x| Some(x.first_end_deep_text()));
+        .and_then(|x| x.find(Name("font")).nth(2))
+        .map(|x| x.first_end_deep_text());
     // p:nth-child(7)
-    let check_in_today_status = check_in_iter
-        .as_mut()
-        .and_then(|x| x.next())
-        .and_then(|x| Some(x.first_end_deep_text()));
+    let check_in_today_status = check_in_p(6).map(|x| x.first_end_deep_text());
 
     /* User groups */
 
@@ -0,0 +1,58 @@
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Instant};
+
+/// Limiter to keep a minimum interval between requests.
+///
+/// Requests are expected to call [RateLimiter::wait] before sending, which sleeps until
+/// `min_interval` passed since the last request.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Minimum interval between two requests.
+    min_interval: Duration,
+
+    /// Time of the last request, `None` if no request sent yet.
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Build a limiter with minimum interval `min_interval` between requests.
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Minimum interval between requests.
+    pub fn min_interval(&self) -> Duration {
+        self.min_interval
+    }
+
+    /// Wait till the next request is allowed.
+    ///
+    /// Concurrent callers are served one by one, each keeping the interval from the previous.
+    pub async fn wait(&self) {
+        let mut last_request = self.last_request.lock().await;
+        if let Some(last) = *last_request {
+            sleep_until(last + self.min_interval).await;
+        }
+        *last_request = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait() {
+        let limiter = RateLimiter::new(Duration::from_millis(50));
+        let start = Instant::now();
+        limiter.wait().await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+        limiter.wait().await;
+        limiter.wait().await;
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -1,12 +1,17 @@
+use crate::client::ApiClient;
 use crate::post::Post;
-use crate::utils::http_get;
+use crate::rate_limit::RateLimiter;
+use crate::utils::deserialize_number;
 use crate::ApiError::{ServerRespError, WebRequestError};
 use crate::{decompress_response_to_string, ErrorResponse};
 use anyhow::{Context, Result};
+use futures::{StreamExt, TryStreamExt};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tm_types::{PostPerPageValue, BASE_URL};
+use std::sync::atomic::{AtomicU32, Ordering};
+use tm_macros::forum_url;
+use tm_types::{ForumId, PageNumber, ThreadId};
 use tracing::{debug, trace};
 
 /// Thread model
@@ -21,16 +26,18 @@ pub struct Thread {
     pub post_list: Vec<Post>,
 
     /// All post count in the thread.
-    #[serde(rename = "totalpost")]
-    pub total_post: String,
+    ///
+    /// The first floor is not included.
+    #[serde(rename = "totalpost", deserialize_with = "deserialize_number")]
+    pub total_post: u32,
 
     /// Post count in each page if page is fulfilled.
-    #[serde(rename = "tpp")]
-    pub post_per_page: PostPerPageValue,
+    #[serde(rename = "tpp", deserialize_with = "deserialize_number")]
+    pub post_per_page: u32,
 
     /// ID of subreddit the thread currently in.
     #[serde(rename = "fid")]
-    pub forum_id: String,
+    pub forum_id: ForumId,
 
     /// Username of author.
     #[serde(rename = "thread_author")]
@@ -59,41 +66,352 @@ pub struct Thread {
     pub points_map: HashMap<String, String>,
 }
 
-// TODO: Some steps in this function are common steps in all APIs, extract them when completed.
+impl Thread {
+    /// Count of all pages in the thread.
+    ///
+    /// Always at least 1.
+    pub fn total_pages(&self) -> u32 {
+        if self.post_per_page == 0 {
+            return 1;
+        }
+        // One more total posts because the first floor not included in post count.
+        (self.total_post + 1).div_ceil(self.post_per_page)
+    }
+
+    /// Find the post on floor `floor` in current page.
+    pub fn find_post_by_floor(&self, floor: usize) -> Option<&Post> {
+        self.post_list.iter().find(|x| x.floor == floor)
+    }
+
+    /// Find the post with post id `pid` in current page.
+    pub fn find_post_by_id(&self, pid: &str) -> Option<&Post> {
+        self.post_list.iter().find(|x| x.id == pid)
+    }
+
+    /// All posts published by user `uid` in current page, in floor order.
+    pub fn posts_by_author(&self, uid: &str) -> Vec<&Post> {
+        self.post_list
+            .iter()
+            .filter(|x| x.author_id == uid)
+            .collect()
+    }
+}
+
 /// Fetch thread content from server.
-#[tracing::instrument]
-pub async fn fetch_thread_content(tid: u32, page: u32) -> Result<Thread> {
-    let target =
-        format!("{BASE_URL}/forum.php?mobile=yes&tsdmapp=1&mod=viewthread&tid={tid}&page={page}");
-    debug!("fetch thread on url {target}");
-    let resp = http_get(target.as_str())
-        .await
-        .context("failed to get thread content")?;
-    if resp.status() != StatusCode::OK {
-        debug!("bad response status: {}", resp.status());
-        return Err(WebRequestError(target, resp.status().to_string()).into());
+///
+/// Use [ApiClient::fetch_thread_content] instead when fetching multiple pages.
+pub async fn fetch_thread_content(tid: ThreadId, page: PageNumber) -> Result<Thread> {
+    ApiClient::new().fetch_thread_content(tid, page).await
+}
+
+/// Fetch all pages in thread.
+///
+/// Use [ApiClient::fetch_thread_all_pages] instead when sending other requests.
+pub async fn fetch_thread_all_pages(tid: ThreadId) -> Result<Vec<Thread>> {
+    ApiClient::new().fetch_thread_all_pages(tid).await
+}
+
+impl ApiClient {
+    /// Fetch all pages in thread, return them in page order.
+    ///
+    /// The first page is fetched to know the count of pages, then the remaining pages are fetched
+    /// concurrently, at most [ApiConfig::max_concurrent_requests] at the same time.
+    ///
+    /// [ApiConfig::max_concurrent_requests]: crate::ApiConfig::max_concurrent_requests
+    pub async fn fetch_thread_all_pages(&self, tid: ThreadId) -> Result<Vec<Thread>> {
+        self.fetch_thread_all_pages_with_progress(tid, |_, _| {})
+            .await
     }
-    let thread_data = decompress_response_to_string(resp)
-        .await
-        .context("when parsing thread data")?
-        // Remove CR and LF
-        // The CR is useless and remove it is safe.
-        // The LF only follows "<br />" which is useless and can be safely removed, too.
-        .replace(['\u{000A}', '\u{000D}'], "");
-
-    // Check if error occurred.
-    // Currently, we are checking error response by try to deserialize the data into pre-defined
-    // format. It's expensive if the deserializing step does not early return, but it shall have.
-    if let Ok(error_resp) = serde_json::from_str::<ErrorResponse>(thread_data.as_str()) {
-        return Err(
-            ServerRespError(error_resp.status, error_resp.message.unwrap_or_default()).into(),
+
+    /// Same as [ApiClient::fetch_thread_all_pages], calls `progress` with the count of fetched
+    /// pages and total pages each time a page is fetched.
+    pub async fn fetch_thread_all_pages_with_progress<F>(
+        &self,
+        tid: ThreadId,
+        progress: F,
+    ) -> Result<Vec<Thread>>
+    where
+        F: Fn(u32, u32),
+    {
+        self.fetch_thread_pages_with_progress(tid, PageNumber::FIRST, None, progress)
+            .await
+    }
+
+    /// Fetch pages in thread within range [`from_page`, `to_page`], return them in page order.
+    ///
+    /// `to_page` is clamped to the last page in thread, `None` means the last page. Return empty
+    /// if `from_page` exceeds the last page or `to_page`.
+    ///
+    /// The page `from_page` is fetched first to know the count of pages, then the remaining pages
+    /// are distributed into [ApiConfig::max_concurrent_requests] slots, each slot fetches its
+    /// pages in order. If the client has a rate limiter, each slot has its own one with the same
+    /// interval, so the rate limit applies per slot.
+    ///
+    /// `progress` is called with the count of fetched pages and the count of pages in range each
+    /// time a page is fetched.
+    ///
+    /// [ApiConfig::max_concurrent_requests]: crate::ApiConfig::max_concurrent_requests
+    #[tracing::instrument(skip(self, progress))]
+    pub async fn fetch_thread_pages_with_progress<F>(
+        &self,
+        tid: ThreadId,
+        from_page: PageNumber,
+        to_page: Option<PageNumber>,
+        progress: F,
+    ) -> Result<Vec<Thread>>
+    where
+        F: Fn(u32, u32),
+    {
+        if to_page.is_some_and(|x| x < from_page) {
+            return Ok(vec![]);
+        }
+
+        // Any page tells the count of pages, fetch the first page in range to save a request.
+        let first_page = self
+            .fetch_thread_content(tid, from_page)
+            .await
+            .with_context(|| format!("when fetching the first page {from_page} in range"))?;
+        let total_pages = first_page.total_pages();
+        debug!("thread {tid} has {total_pages} pages");
+        if from_page.get() > total_pages {
+            return Ok(vec![]);
+        }
+        let to_page = to_page.map_or(total_pages, |x| x.get().min(total_pages));
+        let range_pages = to_page - from_page.get() + 1;
+        progress(1, range_pages);
+
+        let slot_count = self.config.max_concurrent_requests.max(1);
+        let mut slots = vec![vec![]; slot_count];
+        for (idx, page) in (from_page.get() + 1..=to_page)
+            .filter_map(PageNumber::new)
+            .enumerate()
+        {
+            slots[idx % slot_count].push(page);
+        }
+
+        let fetched = AtomicU32::new(1);
+        let (fetched, progress) = (&fetched, &progress);
+        let mut remaining_pages = futures::stream::iter(slots)
+            .map(|pages| {
+                let client = match self.rate_limiter.as_ref() {
+                    Some(v) => self
+                        .clone()
+                        .with_rate_limiter(RateLimiter::new(v.min_interval())),
+                    None => self.clone(),
+                };
+                async move {
+                    let mut slot_pages = Vec::with_capacity(pages.len());
+                    for page in pages {
+                        let thread = client
+                            .fetch_thread_content(tid, page)
+                            .await
+                            .with_context(|| format!("when fetching page {page}"))?;
+                        slot_pages.push((page, thread));
+                        progress(fetched.fetch_add(1, Ordering::Relaxed) + 1, range_pages);
+                    }
+                    Ok::<_, anyhow::Error>(slot_pages)
+                }
+            })
+            .buffer_unordered(slot_count)
+            .try_concat()
+            .await?;
+        remaining_pages.sort_by_key(|(page, _)| *page);
+
+        let mut pages = Vec::with_capacity(range_pages as usize);
+        pages.push(first_page);
+        pages.extend(remaining_pages.into_iter().map(|(_, thread)| thread));
+
+        Ok(pages)
+    }
+
+    // TODO: Some steps in this function are common steps in all APIs, extract them when completed.
+    /// Fetch thread content from server.
+    #[tracing::instrument(skip(self))]
+    pub async fn fetch_thread_content(&self, tid: ThreadId, page: PageNumber) -> Result<Thread> {
+        let target = forum_url!(
+            base = self.config.base_url,
+            "forum.php",
+            mobile = "yes",
+            tsdmapp = 1,
+            mod = "viewthread",
+            tid = tid,
+            page = page
         );
+        debug!("fetch thread on url {target}");
+        let resp = self
+            .get(target.as_str())
+            .await
+            .context("failed to get thread content")?;
+        if resp.status() != StatusCode::OK {
+            debug!("bad response status: {}", resp.status());
+            return Err(WebRequestError(target, resp.status().to_string()).into());
+        }
+        let thread_data = decompress_response_to_string(resp)
+            .await
+            .context("when parsing thread data")?
+            // Remove CR and LF
+            // The CR is useless and remove it is safe.
+            // The LF only follows "<br />" which is useless and can be safely removed, too.
+            .replace(['\u{000A}', '\u{000D}'], "");
+
+        // Check if error occurred.
+        // Currently, we are checking error response by try to deserialize the data into pre-defined
+        // format. It's expensive if the deserializing step does not early return, but it shall have.
+        if let Ok(error_resp) = serde_json::from_str::<ErrorResponse>(thread_data.as_str()) {
+            return Err(
+                ServerRespError(error_resp.status, error_resp.message.unwrap_or_default()).into(),
+            );
+        }
+
+        trace!("thread data: {thread_data}");
+
+        let thread: Thread =
+            serde_json::from_str(thread_data.as_str()).context("when deserializing thread data")?;
+
+        Ok(thread)
     }
+}
+
+/// Summary of a thread in forum thread list.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThreadSummary {
+    /// Thread id.
+    #[serde(deserialize_with = "deserialize_number")]
+    pub tid: ThreadId,
+
+    /// Thread subject.
+    #[serde(rename = "subject")]
+    pub title: String,
+
+    /// Username of author.
+    pub author: String,
+
+    /// Count of replies.
+    #[serde(rename = "replies", deserialize_with = "deserialize_number")]
+    pub reply_count: u32,
+
+    /// Time of last post in thread.
+    #[serde(rename = "lastpost")]
+    pub last_post_time: String,
+}
+
+/// A page of threads in forum.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThreadListPage {
+    /// Threads in current page.
+    pub threads: Vec<ThreadSummary>,
+
+    /// Count of all threads in forum.
+    pub total_threads: u32,
+}
 
-    trace!("thread data: {thread_data}");
+/// Thread list in the response of server.
+#[derive(Deserialize)]
+struct ThreadListResponse {
+    #[serde(rename = "forumthreadlist")]
+    threads: Vec<ThreadSummary>,
 
-    let thread: Thread =
-        serde_json::from_str(thread_data.as_str()).context("when deserializing thread data")?;
+    forum: ThreadListForum,
+}
+
+/// Forum info in thread list response.
+#[derive(Deserialize)]
+struct ThreadListForum {
+    #[serde(rename = "threads", deserialize_with = "deserialize_number")]
+    total_threads: u32,
+}
+
+/// Fetch threads in forum `fid` on page `page`.
+///
+/// Use [ApiClient::fetch_thread_list] instead when fetching multiple pages.
+pub async fn fetch_thread_list(fid: &ForumId, page: PageNumber) -> Result<ThreadListPage> {
+    ApiClient::new().fetch_thread_list(fid, page).await
+}
+
+impl ApiClient {
+    /// Fetch threads in forum `fid` on page `page`.
+    #[tracing::instrument(skip(self))]
+    pub async fn fetch_thread_list(
+        &self,
+        fid: &ForumId,
+        page: PageNumber,
+    ) -> Result<ThreadListPage> {
+        let target = forum_url!(
+            base = self.config.base_url,
+            "forum.php",
+            mobile = "yes",
+            tsdmapp = 1,
+            mod = "forumdisplay",
+            fid = fid,
+            page = page
+        );
+        debug!("fetch thread list on url {target}");
+        let resp = self
+            .get(target.as_str())
+            .await
+            .context("failed to get thread list")?;
+        if resp.status() != StatusCode::OK {
+            debug!("bad response status: {}", resp.status());
+            return Err(WebRequestError(target, resp.status().to_string()).into());
+        }
+        let data = decompress_response_to_string(resp)
+            .await
+            .context("when parsing thread list data")?;
+
+        if let Ok(error_resp) = serde_json::from_str::<ErrorResponse>(data.as_str()) {
+            return Err(
+                ServerRespError(error_resp.status, error_resp.message.unwrap_or_default()).into(),
+            );
+        }
+
+        trace!("thread list data: {data}");
 
-    Ok(thread)
+        let resp: ThreadListResponse =
+            serde_json::from_str(data.as_str()).context("when deserializing thread list data")?;
+
+        Ok(ThreadListPage {
+            threads: resp.threads,
+            total_threads: resp.forum.total_threads,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_pages() {
+        let data = r#"{
+            "subject": "t", "postlist": [], "totalpost": "20", "tpp": 10, "fid": "8",
+            "thread_author": "u", "thread_authorid": 1, "ismoderator": 0, "thread_price": "0",
+            "thread_paid": 0, "extcreditsname": {}
+        }"#;
+        let mut thread: Thread = serde_json::from_str(data).unwrap();
+        assert_eq!(thread.total_post, 20);
+        assert_eq!(thread.post_per_page, 10);
+        assert_eq!(thread.total_pages(), 3);
+        thread.total_post = 19;
+        assert_eq!(thread.total_pages(), 2);
+        thread.post_per_page = 0;
+        assert_eq!(thread.total_pages(), 1);
+    }
+
+    #[test]
+    fn test_deserialize_thread_list() {
+        let data = r#"{
+            "forum": {"fid": "8", "threads": "2"},
+            "forumthreadlist": [
+                {"tid": "1", "subject": "a", "author": "u1", "replies": "3", "lastpost": "2024-1-1 00:00"},
+                {"tid": "2", "subject": "b", "author": "u2", "replies": 0, "lastpost": "2024-1-2 00:00"}
+            ]
+        }"#;
+        let resp: ThreadListResponse = serde_json::from_str(data).unwrap();
+        assert_eq!(resp.forum.total_threads, 2);
+        assert_eq!(resp.threads.len(), 2);
+        assert_eq!(resp.threads[0].reply_count, 3);
+        assert_eq!(resp.threads[1].reply_count, 0);
+        assert_eq!(resp.threads[1].title, "b");
+        assert_eq!(resp.threads[1].tid, ThreadId(2));
+    }
 }
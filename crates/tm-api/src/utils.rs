@@ -1,19 +1,101 @@
-use reqwest::header::ACCEPT_ENCODING;
-use reqwest::{header, Error, Response};
+use crate::auth::SessionToken;
+use crate::client::ApiConfig;
+use crate::rate_limit::RateLimiter;
+use reqwest::header::{ACCEPT_ENCODING, COOKIE};
+use reqwest::{header, Client, Error, Response};
+use serde::Deserialize;
 use std::future::Future;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::warn;
 
-const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36";
+pub(crate) const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36";
 
+/// Build a http client with headers used in all requests.
+pub(crate) fn build_client(config: &ApiConfig) -> Client {
+    let mut headers = header::HeaderMap::new();
+    headers.insert(ACCEPT_ENCODING, "gzip, deflate, br, zstd".parse().unwrap());
+    let mut builder = Client::builder()
+        .user_agent(config.user_agent.as_str())
+        .default_headers(headers);
+    if let Some(timeout) = config.timeout {
+        builder = builder.timeout(timeout);
+    }
+    builder.build().unwrap()
+}
+
+/// Send a GET request without retry.
+///
+/// Send with the cookie in `session` if any.
 pub(crate) fn http_get(
+    client: &Client,
     url: impl AsRef<str>,
+    session: Option<&SessionToken>,
 ) -> impl Future<Output = Result<Response, Error>> + Sized {
-    let mut headers = header::HeaderMap::new();
-    headers.insert(ACCEPT_ENCODING, "gzip, deflate, br, zstd".parse().unwrap());
-    reqwest::Client::builder()
-        .user_agent(USER_AGENT)
-        .default_headers(headers)
-        .build()
-        .unwrap()
-        .get(url.as_ref())
-        .send()
+    let mut req = client.get(url.as_ref());
+    if let Some(session) = session {
+        req = req.header(COOKIE, session.cookie.as_str());
+    }
+    req.send()
+}
+
+/// Send a GET request, retry on network errors and 5xx responses.
+///
+/// Try at most `max_attempts` times, the delay between attempts starts at `base_delay` and
+/// doubles after each retry, up to `max_delay`. When all attempts failed, the last error or 5xx
+/// response is returned.
+///
+/// Every attempt, retries included, waits for `rate_limiter` before sending.
+pub(crate) async fn http_get_with_retry(
+    client: &Client,
+    url: impl AsRef<str>,
+    session: Option<&SessionToken>,
+    rate_limiter: Option<&RateLimiter>,
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+) -> Result<Response, Error> {
+    let url = url.as_ref();
+    let mut delay = base_delay.min(max_delay);
+    let mut attempt = 1;
+    loop {
+        if let Some(rate_limiter) = rate_limiter {
+            rate_limiter.wait().await;
+        }
+        match http_get(client, url, session).await {
+            Ok(resp) if resp.status().is_server_error() && attempt < max_attempts => {
+                warn!(
+                    "attempt {attempt}/{max_attempts} on {url} got status {}, retry in {delay:?}",
+                    resp.status()
+                );
+            }
+            Err(e) if attempt < max_attempts => {
+                warn!("attempt {attempt}/{max_attempts} on {url} failed: {e}, retry in {delay:?}");
+            }
+            v => return v,
+        }
+        sleep(delay).await;
+        delay = delay.saturating_mul(2).min(max_delay);
+        attempt += 1;
+    }
+}
+
+/// Deserialize a number that server may send as either number or string.
+pub(crate) fn deserialize_number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::str::FromStr + Deserialize<'de>,
+    T::Err: std::fmt::Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString<T> {
+        Number(T),
+        String(String),
+    }
+
+    match NumberOrString::<T>::deserialize(deserializer)? {
+        NumberOrString::Number(v) => Ok(v),
+        NumberOrString::String(v) => v.trim().parse::<T>().map_err(serde::de::Error::custom),
+    }
 }
@@ -24,4 +24,19 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_bbx_shorthand_format_args() {
+        let name = "W";
+        assert_eq!(bbx!(b { "{name} and {name:>2}" }), "[b]W and  W[/b]");
+        assert_eq!(bbx!(b { "{name} {{x}}" }), "[b]W {x}[/b]");
+        assert_eq!(bbx!(b { "plain {{x}}" }), "[b]plain {x}[/b]");
+        assert_eq!(bbx!(b { "plain" }), "[b]plain[/b]");
+    }
+
+    #[test]
+    fn test_bbx_compile_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("ui/*.rs");
+    }
 }
@@ -0,0 +1,5 @@
+use tm_bbcode_macro::bbx;
+
+fn main() {
+    let _ = bbx!(url { {}, "link" });
+}
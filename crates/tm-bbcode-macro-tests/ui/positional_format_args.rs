@@ -0,0 +1,7 @@
+use tm_bbcode_macro::bbx;
+
+fn main() {
+    let name = "W";
+    let _ = bbx!(b { "{name} literal {}" });
+    let _ = bbx!(b { "indexed {0}" });
+}
@@ -1,8 +1,9 @@
 use proc_macro::TokenStream;
 use proc_macro2 as pm2;
-use quote::{quote, ToTokens, TokenStreamExt};
+use quote::{quote, quote_spanned, ToTokens, TokenStreamExt};
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::{braced, parenthesized, parse_macro_input};
 
 /// The trait `BBCode` defines common methods for all types that need to
@@ -34,12 +35,30 @@ enum Node {
 
     /// Text node represents plain text, does not contain bbcode tags.
     Text(Text),
+
+    /// Conditional node renders children in one of its branches.
+    ConditionalChild(ConditionalChild),
+
+    /// For loop node renders children once for each item in an iterator.
+    ForChild(ForChild),
+
+    /// Optional node renders an `Option` text, nothing if `None`.
+    OptionalChild(OptionalChild),
 }
 
 impl Parse for Node {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        // Either an element or a text.
-        if input.peek(syn::Ident) {
+        // Either a conditional, an element or a text.
+        if input.peek(syn::Token![if]) {
+            // if cond { children } else { children }
+            input.parse().map(Node::ConditionalChild)
+        } else if input.peek(syn::Token![for]) {
+            // for pat in expr { children }
+            input.parse().map(Node::ForChild)
+        } else if input.peek(syn::Token![?]) {
+            // ?optional_expr
+            input.parse().map(Node::OptionalChild)
+        } else if input.peek(syn::Ident) {
             if input.peek2(syn::token::Brace) {
                 // ident {
                 input.parse().map(Node::Element)
@@ -61,7 +80,10 @@ impl Parse for Node {
             Err(syn::Error::new(input.span(), "unexpected '{': If you intend to write element, add a name before '{'; Or attribute that shall be placed before children nodes"))
         } else {
             // Unknown node type.
-            Err(syn::Error::new(input.span(), "invalid node type"))
+            Err(syn::Error::new(
+                input.span(),
+                "invalid node type: expected element `name { .. }`, text, `(format, args)`, `if`, `for` or `?option`",
+            ))
         }
     }
 }
@@ -72,11 +94,98 @@ impl BBCode for Node {
         match self {
             Node::Element(v) => v.to_tokens(&mut t),
             Node::Text(v) => v.to_tokens(&mut t),
+            Node::ConditionalChild(v) => v.to_tokens(&mut t),
+            Node::ForChild(v) => v.to_tokens(&mut t),
+            Node::OptionalChild(v) => v.to_tokens(&mut t),
         }
         tokens.push(t)
     }
 }
 
+/// Render `nodes` into an expression evaluating to the joined bbcode `String`.
+fn render_nodes(nodes: &Punctuated<Node, syn::Token![,]>) -> pm2::TokenStream {
+    let mut output = vec![];
+    for node in nodes {
+        node.to_bbcode(&mut output);
+    }
+    quote! {
+        {let v: Vec<String> = vec![#(#output),*]; v.join("")}
+    }
+}
+
+/// Conditional children in format `if cond { children } else { children }`.
+///
+/// `cond` is a rust expression evaluates to `bool`, `else` branch is optional and can be followed
+/// by another `if`.
+#[derive(Debug)]
+struct ConditionalChild {
+    /// Condition expression.
+    cond: syn::Expr,
+
+    /// Children rendered when `cond` is true.
+    then_branch: Punctuated<Node, syn::Token![,]>,
+
+    /// Optional branch rendered when `cond` is false.
+    else_branch: Option<ElseBranch>,
+}
+
+/// The `else` part in [ConditionalChild].
+#[derive(Debug)]
+enum ElseBranch {
+    /// `else if cond { children }`
+    If(Box<ConditionalChild>),
+
+    /// `else { children }`
+    Nodes(Punctuated<Node, syn::Token![,]>),
+}
+
+impl Parse for ConditionalChild {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<syn::Token![if]>()?;
+        // Same as the condition in `if` expression, the brace after it belongs to the branch.
+        let cond = syn::Expr::parse_without_eager_brace(input)?;
+        let content;
+        braced!(content in input);
+        let then_branch = content.parse_terminated(Node::parse, syn::Token![,])?;
+
+        let else_branch = if input.parse::<Option<syn::Token![else]>>()?.is_some() {
+            if input.peek(syn::Token![if]) {
+                Some(ElseBranch::If(Box::new(input.parse()?)))
+            } else {
+                let content;
+                braced!(content in input);
+                Some(ElseBranch::Nodes(
+                    content.parse_terminated(Node::parse, syn::Token![,])?,
+                ))
+            }
+        } else {
+            None
+        };
+
+        Ok(Self {
+            cond,
+            then_branch,
+            else_branch,
+        })
+    }
+}
+
+impl ToTokens for ConditionalChild {
+    fn to_tokens(&self, tokens: &mut pm2::TokenStream) {
+        let cond = &self.cond;
+        let then_branch = render_nodes(&self.then_branch);
+        let else_branch = match &self.else_branch {
+            Some(ElseBranch::If(v)) => v.to_token_stream(),
+            Some(ElseBranch::Nodes(v)) => render_nodes(v),
+            None => quote! { String::new() },
+        };
+        let cond = quote_spanned! {cond.span()=> { let cond: bool = #cond; cond }};
+        tokens.append_all(quote! {
+            if #cond { #then_branch } else { #else_branch }
+        });
+    }
+}
+
 /// Text holds plain text, no bbcode.
 #[derive(Debug)]
 enum Text {
@@ -87,12 +196,33 @@ enum Text {
 impl ToTokens for Text {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         match self {
-            Text::TextExpr(v) => {
+            Text::TextExpr(syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            })) if lit.value().contains(['{', '}']) => match shorthand_format_args(lit) {
+                Ok(args) => {
+                    let args = args
+                        .into_iter()
+                        .map(|x| syn::Ident::new(x.as_str(), lit.span()));
+                    tokens.append_all(quote! {
+                        format!(#lit, #(#args = #args),*)
+                    });
+                }
+                Err(e) => tokens.append_all(e.to_compile_error()),
+            },
+            Text::TextExpr(v @ syn::Expr::Lit(..)) => {
                 let data = v.clone();
-                tokens.append_all(quote! {
+                tokens.append_all(quote_spanned! {data.span()=>
                     String::from(#data)
                 });
             }
+            Text::TextExpr(v) => {
+                // Dispatched by the type of `v`, see `text_child_helpers`.
+                let data = v.clone();
+                tokens.append_all(quote_spanned! {data.span()=>
+                    (&__BbxTextChild(::std::cell::Cell::new(Some(#data)))).__bbx_text()
+                });
+            }
             Text::FormattedArgs(v) => v.to_tokens(tokens),
         }
     }
@@ -121,6 +251,46 @@ impl BBCode for Text {
     }
 }
 
+/// Collect names in shorthand format args `{name}` in string literal `lit`, deduplicated and in
+/// order of appearance.
+///
+/// Escaped braces `{{` and `}}` are skipped. Format spec after the name is allowed, e.g.
+/// `{name:>8}`.
+///
+/// Positional args `{}` and `{0}` are not allowed as there is no arg to fill them.
+fn shorthand_format_args(lit: &syn::LitStr) -> syn::Result<Vec<String>> {
+    let value = lit.value();
+    let mut names = Vec::<String>::new();
+    let mut chars = value.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '{' {
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            continue;
+        }
+        let mut placeholder = String::new();
+        for ch in chars.by_ref() {
+            if ch == '}' {
+                break;
+            }
+            placeholder.push(ch);
+        }
+        let name = placeholder.split(':').next().unwrap_or_default().trim();
+        if syn::parse_str::<syn::Ident>(name).is_err() {
+            return Err(syn::Error::new(
+                lit.span(),
+                format!("positional format arg {{{placeholder}}} is not supported in text, use {{name}} referring to a variable, or escape braces as {{{{ and }}}}"),
+            ));
+        }
+        if !names.iter().any(|x| x == name) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
 /// Text holds plain text, no bbcode.
 #[derive(Debug)]
 struct TextLiteral {
@@ -153,6 +323,76 @@ impl BBCode for TextLiteral {
     }
 }
 
+/// For loop children in format `for pat in expr { children }`.
+///
+/// `expr` is anything implements `IntoIterator`, children are rendered for each item and joined.
+#[derive(Debug)]
+struct ForChild {
+    /// Pattern binding each item.
+    pat: syn::Pat,
+
+    /// Expression to iterate over.
+    expr: syn::Expr,
+
+    /// Children rendered for each item.
+    body: Punctuated<Node, syn::Token![,]>,
+}
+
+impl Parse for ForChild {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<syn::Token![for]>()?;
+        let pat = syn::Pat::parse_multi_with_leading_vert(input)?;
+        input.parse::<syn::Token![in]>()?;
+        let expr = syn::Expr::parse_without_eager_brace(input)?;
+        let content;
+        braced!(content in input);
+        let body = content.parse_terminated(Node::parse, syn::Token![,])?;
+
+        Ok(Self { pat, expr, body })
+    }
+}
+
+impl ToTokens for ForChild {
+    fn to_tokens(&self, tokens: &mut pm2::TokenStream) {
+        let (pat, expr) = (&self.pat, &self.expr);
+        let body = render_nodes(&self.body);
+        let expr = quote_spanned! {expr.span()=> IntoIterator::into_iter(#expr)};
+        tokens.append_all(quote! {
+            #expr
+                .map(|#pat| #body)
+                .collect::<Vec<String>>()
+                .join("")
+        });
+    }
+}
+
+/// Optional text in format `?expr`.
+///
+/// `expr` evaluates to `Option<T>` where `String: From<T>`, `None` renders nothing.
+#[derive(Debug)]
+struct OptionalChild {
+    /// Expression of the `Option`.
+    expr: syn::Expr,
+}
+
+impl Parse for OptionalChild {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<syn::Token![?]>()?;
+        Ok(Self {
+            expr: input.parse()?,
+        })
+    }
+}
+
+impl ToTokens for OptionalChild {
+    fn to_tokens(&self, tokens: &mut pm2::TokenStream) {
+        let expr = &self.expr;
+        tokens.append_all(quote_spanned! {expr.span()=>
+            Option::map(#expr, String::from).unwrap_or_default()
+        });
+    }
+}
+
 /// Element represents a bbcode tag.
 ///
 /// BBCode format: `[$name=$attr]$children[/$name]`
@@ -267,6 +507,12 @@ impl Parse for Attr {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let content;
         let _brace = braced!(content in input);
+        if content.is_empty() {
+            return Err(syn::Error::new(
+                _brace.span.join(),
+                "empty attribute: add the attribute value in '{}' or remove it",
+            ));
+        }
 
         Ok(Self {
             _brace,
@@ -298,14 +544,17 @@ impl Parse for AttrValue {
             return Ok(Self::FormattedArgs(input.parse()?));
         }
 
-        Err(syn::Error::new(input.span(), "invalid AttrValue"))
+        Err(syn::Error::new(
+            input.span(),
+            "invalid attribute value: expected expression or `(format, args)`",
+        ))
     }
 }
 
 impl ToTokens for AttrValue {
     fn to_tokens(&self, tokens: &mut pm2::TokenStream) {
         match &self {
-            AttrValue::AttrExpr(v) => tokens.append_all(quote! {
+            AttrValue::AttrExpr(v) => tokens.append_all(quote_spanned! {v.span()=>
                 #v.to_string()
             }),
             AttrValue::FormattedArgs(v) => {
@@ -322,8 +571,8 @@ impl ToTokens for AttrValue {
 #[derive(Debug)]
 struct FormattedArgs {
     _paren: syn::token::Paren,
-    format_string: pm2::Literal,
-    _punct: pm2::Punct,
+    format_string: syn::LitStr,
+    _comma: syn::Token![,],
     args: Punctuated<syn::Expr, syn::Token![,]>,
     // _open ends here.
 }
@@ -342,14 +591,24 @@ impl Parse for FormattedArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let content;
         let _paren = parenthesized!(content in input);
-        let format_string = content.parse::<pm2::Literal>()?;
-        let _punct = content.parse()?;
+        let format_string = content.parse::<syn::LitStr>().map_err(|e| {
+            syn::Error::new(
+                e.span(),
+                "expected format string literal as the first one in `(format, args)`",
+            )
+        })?;
+        let _comma = content.parse().map_err(|_| {
+            syn::Error::new(
+                format_string.span(),
+                "expected `,` and args after format string, use the text literal directly if no args",
+            )
+        })?;
         let args = content.parse_terminated(syn::Expr::parse, syn::Token![,])?;
 
         Ok(Self {
             _paren,
             format_string,
-            _punct,
+            _comma,
             args,
         })
     }
@@ -515,17 +774,220 @@ impl BBCode for FormattedArgs {
 ///
 /// * Variable `crates_io_url` in attributes.
 /// * Variable `the_rust` in format args `The {}`.
-///   * **Note that shorthand format args `The {rust}` is not supported yet.**
 /// * Variable `package_registry` as text child.
+///
+/// ### Render text with shorthand format args
+///
+/// Text literal is formatted like `format!("{name}")`, where `name` refers to the local variable.
+/// Use `{{` and `}}` to write braces in text. Positional args like `{}` are not allowed.
+///
+/// ```rust
+/// use tm_bbcode_macro::bbx;
+///
+/// let name = "World";
+/// let count = 3;
+///
+/// let bbcode = bbx!(
+///     b {
+///         "Hello {name}"
+///     },
+///     i {
+///         "{name} has {count:>2} {{items}}"
+///     },
+///     u {
+///         "no {{}} args"
+///     }
+/// );
+///
+/// assert_eq!(bbcode, "[b]Hello World[/b][i]World has  3 {items}[/i][u]no {} args[/u]");
+/// ```
+///
+/// ### Render conditional children
+///
+/// `if` and `else` works like rust, the condition is a rust expression evaluates to `bool` and
+/// each branch holds children nodes. Nothing is rendered if no branch matches.
+///
+/// ```rust
+/// use tm_bbcode_macro::bbx;
+///
+/// let points = 120;
+///
+/// let bbcode = bbx!(
+///     b {
+///         if points >= 100 {
+///             color { {"red"}, "reached" }
+///         } else if points >= 50 {
+///             "halfway"
+///         } else {
+///             "not yet"
+///         }
+///     },
+///     i {
+///         if points < 100 { "unreachable" }
+///     }
+/// );
+///
+/// assert_eq!(bbcode, "[b][color=red]reached[/color][/b][i][/i]");
+/// ```
+///
+/// ### Render children in for loop
+///
+/// `for` works like rust, children are rendered once for each item and joined together.
+///
+/// ```rust
+/// use tm_bbcode_macro::bbx;
+///
+/// let items = vec![String::from("a"), String::from("b")];
+/// let users = [("alice", 3), ("bob", 5)];
+///
+/// let bbcode = bbx!(
+///     list {
+///         for item in &items {
+///             li { item.as_str() }
+///         }
+///     },
+///     table {
+///         for (name, points) in users.iter() {
+///             tr { td { name.to_string() }, td { ("{}", points) } }
+///         }
+///     }
+/// );
+///
+/// assert_eq!(bbcode, "[list][li]a[/li][li]b[/li][/list][table][tr][td]alice[/td][td]3[/td][/tr][tr][td]bob[/td][td]5[/td][/tr][/table]");
+/// ```
+///
+/// ### Render optional text
+///
+/// Prefix `?` to an expression evaluates to `Option`, `Some` value is rendered as text and `None`
+/// renders nothing.
+///
+/// ```rust
+/// use tm_bbcode_macro::bbx;
+///
+/// let nickname = Some(String::from("foo"));
+/// let signature: Option<&str> = None;
+///
+/// let bbcode = bbx!(
+///     u { ?nickname.as_ref().map(|x| x.to_uppercase()) },
+///     b { ?nickname },
+///     i { ?signature }
+/// );
+///
+/// assert_eq!(bbcode, "[u]FOO[/u][b]foo[/b][i][/i]");
+/// ```
+///
+/// ### Render collection of text
+///
+/// Text child can also be a collection, anything implements `IntoIterator` with items convertible
+/// to `String`. Items are joined together without separator.
+///
+/// ```rust
+/// use tm_bbcode_macro::bbx;
+///
+/// let lines = vec![String::from("line 1\n"), String::from("line 2\n")];
+/// let words = ["a", "b", "c"];
+///
+/// let bbcode = bbx!(
+///     quote { lines },
+///     b { words },
+///     i { words.iter().map(|x| x.to_uppercase()) }
+/// );
+///
+/// assert_eq!(bbcode, "[quote]line 1\nline 2\n[/quote][b]abc[/b][i]ABC[/i]");
+/// ```
 #[proc_macro]
 pub fn bbx(input: TokenStream) -> TokenStream {
     let node_root = parse_macro_input!(input as NodeRoot);
-    let mut output = vec![];
-    for code in node_root.0.into_iter() {
-        code.to_bbcode(&mut output);
+    let helpers = text_child_helpers();
+    let output = render_nodes(&node_root.0);
+    quote! {
+        {#helpers #output}
     }
-    let expanded = quote! {
-        {let v: Vec<String> = vec![#(#output),*]; v.join("")}
-    };
-    expanded.into()
+    .into()
+}
+
+/// Helper items converting text child expressions into `String`, defined once in each `bbx`
+/// expansion.
+///
+/// Text child can be any type `T` where `String: From<T>`, or any `T: IntoIterator` with items
+/// convertible to `String`, e.g. `Vec<String>`, where items are joined together.
+///
+/// Rust does not allow overlapping impls on the two bounds, so choose the impl by method
+/// resolution: The child is wrapped in `__BbxTextChild` and both traits are implemented on it,
+/// only the one whose bounds hold is applicable for the concrete type.
+fn text_child_helpers() -> pm2::TokenStream {
+    quote! {
+        #[allow(dead_code)]
+        struct __BbxTextChild<T>(::std::cell::Cell<Option<T>>);
+
+        #[allow(dead_code)]
+        trait __BbxText {
+            fn __bbx_text(&self) -> String;
+        }
+
+        impl<T: Into<String>> __BbxText for &__BbxTextChild<T> {
+            fn __bbx_text(&self) -> String {
+                self.0.take().map(Into::into).unwrap_or_default()
+            }
+        }
+
+        #[allow(dead_code)]
+        trait __BbxTextIter {
+            fn __bbx_text(&self) -> String;
+        }
+
+        impl<T> __BbxTextIter for __BbxTextChild<T>
+        where
+            T: IntoIterator,
+            T::Item: Into<String>,
+        {
+            fn __bbx_text(&self) -> String {
+                self.0
+                    .take()
+                    .into_iter()
+                    .flatten()
+                    .map(Into::into)
+                    .collect::<Vec<String>>()
+                    .join("")
+            }
+        }
+    }
+}
+
+/// Compose bbcode attribute from multiple parts.
+///
+/// Each part is an expression implementing `ToString`, parts are joined by `,` which is the
+/// separator of values in attribute, e.g. `[table=72,#ffffff]`.
+///
+/// The result is a `String`, use it in attribute of `bbx`.
+///
+/// ```rust
+/// use tm_bbcode_macro::{bbx, bbx_attr};
+///
+/// let width = 72;
+///
+/// assert_eq!(bbx_attr!(width, "align=center"), "72,align=center");
+///
+/// let bbcode = bbx!(
+///     table {
+///         {bbx_attr!(width, "#ffffff")},
+///         "content"
+///     }
+/// );
+///
+/// assert_eq!(bbcode, "[table=72,#ffffff]content[/table]");
+/// ```
+#[proc_macro]
+pub fn bbx_attr(input: TokenStream) -> TokenStream {
+    let parts =
+        parse_macro_input!(input with Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated);
+    let parts = parts.iter().map(|x| {
+        quote_spanned! {x.span()=>
+            ToString::to_string(&#x)
+        }
+    });
+    quote! {
+        {let v: Vec<String> = vec![#(#parts),*]; v.join(",")}
+    }
+    .into()
 }
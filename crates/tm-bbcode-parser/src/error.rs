@@ -0,0 +1,60 @@
+/// Errors when parsing bbcode.
+///
+/// `position` is the index of the token caused the error, `location` is the 1-based line and
+/// column number where that token starts, if known.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    /// Tag head `[$name]` does not have a matching tail.
+    #[error("unclosed tag \"{name}\" {}", describe_position(*.position, .location))]
+    UnclosedTag {
+        name: String,
+        position: usize,
+        location: Option<(usize, usize)>,
+    },
+
+    /// Tag tail `[/$name]` does not have a matching head.
+    #[error("unexpected closing tag \"{name}\" {}", describe_position(*.position, .location))]
+    UnexpectedClosingTag {
+        name: String,
+        position: usize,
+        location: Option<(usize, usize)>,
+    },
+
+    /// Tag name is not a known bbcode tag.
+    #[error("unknown tag \"{name}\" {}", describe_position(*.position, .location))]
+    UnknownTag {
+        name: String,
+        position: usize,
+        location: Option<(usize, usize)>,
+    },
+
+    /// Tags nested deeper than the allowed max depth.
+    #[error("tag nested deeper than {max_depth} {}", describe_position(*.position, .location))]
+    TooDeep {
+        max_depth: usize,
+        position: usize,
+        location: Option<(usize, usize)>,
+    },
+
+    /// Invalid input found by lexer.
+    ///
+    /// `text` is the invalid input.
+    #[error("{message} {}", describe_position(*.position, .location))]
+    LexError {
+        message: String,
+        text: String,
+        position: usize,
+        location: Option<(usize, usize)>,
+    },
+
+    /// Nothing to parse.
+    #[error("empty input")]
+    EmptyInput,
+}
+
+fn describe_position(position: usize, location: &Option<(usize, usize)>) -> String {
+    match location {
+        Some((line, column)) => format!("at line {line} column {column}"),
+        None => format!("at token {position}"),
+    }
+}
@@ -1,7 +1,7 @@
 use std::str::Chars;
 
 use crate::scanner::Scanner;
-use crate::token::{TagHead, TagTail, Token};
+use crate::token::{LexError, TagHead, TagTail, Token};
 use crate::{CLOSE, EQUAL, OPEN, SLASH};
 
 pub struct Lexer {
@@ -13,6 +13,9 @@ pub struct Lexer {
 
     /// Scanned tokens.
     tokens: Vec<Token>,
+
+    /// Line and column number where each token in `tokens` starts.
+    locations: Vec<(usize, usize)>,
 }
 
 impl Lexer {
@@ -21,12 +24,21 @@ impl Lexer {
             source: Scanner::new(data),
             start: 0,
             tokens: vec![],
+            locations: vec![],
         }
     }
 
+    /// Construct from a string slice.
+    ///
+    /// Characters are copied into the lexer, `s` does not need to outlive it.
+    pub fn new_from_str(s: &str) -> Self {
+        Self::new(s.chars())
+    }
+
     /// Run the process.
     pub fn scan(&mut self) {
         while let Some(ch) = self.source.next() {
+            let location = self.source.location(self.start);
             let token = match ch {
                 OPEN => self.scan_head_or_tail(),
                 _ => self.scan_text(),
@@ -35,6 +47,7 @@ impl Lexer {
 
             if let Some(token) = token {
                 self.tokens.push(token);
+                self.locations.push(location);
             }
         }
     }
@@ -43,6 +56,19 @@ impl Lexer {
         println!("{:#?}", self.tokens);
     }
 
+    /// Consume the lexer and return all scanned tokens.
+    ///
+    /// Call [Lexer::scan] before this, otherwise there is no token.
+    pub fn into_tokens(self) -> Vec<Token> {
+        self.tokens
+    }
+
+    /// Consume the lexer and return all scanned tokens with the 1-based line and column number
+    /// where each token starts.
+    pub fn into_tokens_with_locations(self) -> (Vec<Token>, Vec<(usize, usize)>) {
+        (self.tokens, self.locations)
+    }
+
     /// Try construct a [Token::Head] from input.
     ///
     /// The caller shall ensure current position is on the `[`.
@@ -59,15 +85,18 @@ impl Lexer {
                 return Some(token);
             } else if ch == OPEN {
                 // Another `[` before the tag closes, invalid head.
-                // Keep the unexpected `[` out of text.
+                // Keep the unexpected `[` out of the error.
                 self.source.back();
-                let token = self.collect_text();
+                let token = self.collect_error("unexpected '[' in tag");
                 self.start = self.source.position();
                 return Some(token);
             }
         }
 
-        self.try_fallback()
+        // Reached the end before the tag closes.
+        let token = self.collect_error("unclosed tag at end of input");
+        self.start = self.source.position();
+        Some(token)
     }
 
     /// The caller shall ensure current position is on the `[`.
@@ -84,15 +113,18 @@ impl Lexer {
                 return Some(token);
             } else if ch == OPEN {
                 // Another `[` before the tag closes, invalid head.
-                // Keep the unexpected `[` out of text.
+                // Keep the unexpected `[` out of the error.
                 self.source.back();
-                let token = self.collect_text();
+                let token = self.collect_error("unexpected '[' in tag");
                 self.start = self.source.position();
                 return Some(token);
             }
         }
 
-        self.try_fallback()
+        // Reached the end before the tag closes.
+        let token = self.collect_error("unclosed tag at end of input");
+        self.start = self.source.position();
+        Some(token)
     }
 
     /// Reached the first character of tag head or tail, which is exactly a `[`.
@@ -144,6 +176,19 @@ impl Lexer {
         )
     }
 
+    /// Consume chars between `start` and `curr` as invalid input.
+    fn collect_error(&self, message: &str) -> Token {
+        Token::Error(LexError {
+            message: message.to_string(),
+            position: self.start,
+            text: self
+                .source
+                .get_range(self.start, self.source.position())
+                .iter()
+                .collect::<String>(),
+        })
+    }
+
     /// The caller shall ensure current range is on the first and last character
     /// of tag:
     ///
@@ -157,18 +202,18 @@ impl Lexer {
         let head_content = self.source.get_range(self.start, self.source.position());
 
         // The start position + 1 to skip `[` and end position -1 to exclude `]`
-        let head_tag = match head_content.into_iter().position(|x| x == &EQUAL) {
+        let head_tag = match head_content.iter().position(|x| x == &EQUAL) {
             Some(v) => TagHead {
-                name: head_content[1..v].into_iter().collect::<String>(),
+                name: head_content[1..v].iter().collect::<String>(),
                 attr: Some(
                     head_content[v + 1..head_content.len() - 1]
-                        .into_iter()
+                        .iter()
                         .collect::<String>(),
                 ),
             },
             None => TagHead {
                 name: head_content[1..head_content.len() - 1]
-                    .into_iter()
+                    .iter()
                     .collect::<String>(),
                 attr: None,
             },
@@ -197,3 +242,41 @@ impl Lexer {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let mut lexer = Lexer::new_from_str("a[color=Red]b[/color][c");
+        lexer.scan();
+        assert_eq!(
+            lexer.into_tokens(),
+            vec![
+                Token::Text(String::from("a")),
+                Token::Head(TagHead {
+                    name: String::from("color"),
+                    attr: Some(String::from("Red")),
+                }),
+                Token::Text(String::from("b")),
+                Token::Tail(TagTail {
+                    name: String::from("color"),
+                }),
+                Token::Error(LexError {
+                    message: String::from("unclosed tag at end of input"),
+                    position: 21,
+                    text: String::from("[c"),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_locations() {
+        let mut lexer = Lexer::new_from_str("a\n[b]c\n\nd[/b]");
+        lexer.scan();
+        let (_, locations) = lexer.into_tokens_with_locations();
+        assert_eq!(locations, vec![(1, 1), (2, 1), (2, 4), (4, 2)]);
+    }
+}
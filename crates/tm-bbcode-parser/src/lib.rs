@@ -1,12 +1,413 @@
+pub mod error;
 pub mod lexer;
 pub mod parser;
-mod scanner;
+pub mod scanner;
 pub mod tag;
-pub(crate) mod token;
+pub mod token;
+pub mod visitor;
 
 const OPEN: char = '[';
 const CLOSE: char = ']';
 const SLASH: char = '/';
 const EQUAL: char = '=';
 
-pub fn parse_bbcode(data: impl AsRef<str>) {}
+use crate::error::ParseError;
+use crate::lexer::Lexer;
+use crate::parser::{ParseOptions, ParsedNode, Parser};
+use crate::token::Token;
+
+/// Parse bbcode text into a tree of [ParsedNode]s with default [ParseOptions].
+pub fn parse_bbcode(data: impl AsRef<str>) -> Result<Vec<ParsedNode>, ParseError> {
+    parse_bbcode_with_options(data, ParseOptions::default())
+}
+
+/// Parse bbcode text into a tree of [ParsedNode]s.
+///
+/// Use `options` to configure the parsing behavior.
+pub fn parse_bbcode_with_options(
+    data: impl AsRef<str>,
+    options: ParseOptions,
+) -> Result<Vec<ParsedNode>, ParseError> {
+    let mut lexer = Lexer::new_from_str(data.as_ref());
+    lexer.scan();
+    let (tokens, locations) = lexer.into_tokens_with_locations();
+    Parser::with_options(tokens, options)
+        .with_locations(locations)
+        .parse()
+}
+
+/// Remove all bbcode tags in `input`, only keep plain text.
+///
+/// This is a shortcut working on lexer tokens directly, tags are dropped no matter they are
+/// matched or not.
+///
+/// Invalid input is kept as plain text, use [parse_bbcode_with_options] in strict mode to find
+/// them.
+pub fn strip_bbcode(input: &str) -> String {
+    let mut lexer = Lexer::new_from_str(input);
+    lexer.scan();
+    let mut text = String::new();
+    for token in lexer.into_tokens() {
+        match token {
+            Token::Text(v) => text.push_str(v.as_str()),
+            Token::Head(..) | Token::Tail(..) => continue,
+            Token::Error(err) => text.push_str(err.text.as_str()),
+        }
+    }
+    text
+}
+
+/// Remove tags not in `allowed_tag_names` from `input`.
+///
+/// Tag names are compared case-insensitively. Children of removed tags are kept, only the tag
+/// itself is stripped.
+///
+/// Tags not allowed are removed no matter they are matched or not, so unclosed tags like
+/// `[url=...]` can not get through. Unmatched allowed tags are kept as plain text.
+///
+/// Brackets in text are escaped with [escape_bbcode], otherwise text around a removed tag may
+/// join into a new tag, like `[ur[x]l]`.
+///
+/// Return an empty string if allowed tags are nested deeper than [parser::DEFAULT_MAX_DEPTH].
+pub fn sanitize_bbcode(input: &str, allowed_tag_names: &[&str]) -> String {
+    let is_allowed = |name: &str| {
+        allowed_tag_names
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(name))
+    };
+
+    let mut lexer = Lexer::new_from_str(input);
+    lexer.scan();
+    let tokens = lexer
+        .into_tokens()
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Head(ref head) if !is_allowed(head.name.as_str()) => None,
+            Token::Tail(ref tail) if !is_allowed(tail.name.as_str()) => None,
+            Token::Head(..) | Token::Tail(..) => Some(token),
+            Token::Text(text) => Some(Token::Text(escape_bbcode(text.as_str()))),
+            Token::Error(err) => Some(Token::Text(escape_bbcode(err.text.as_str()))),
+        })
+        .collect::<Vec<_>>();
+
+    // Non-strict parsing only fails on empty input or tags nested too deep.
+    match Parser::new(tokens).parse() {
+        Ok(nodes) => nodes.iter().map(|x| x.to_bbcode()).collect(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Escape bbcode special characters in `input`, `[` becomes `&#91;` and `]` becomes `&#93;`.
+///
+/// The forum renders the html entities as brackets, but does not recognize them as tags.
+///
+/// Use it on user supplied text before embedding it in bbcode, so that the text can not
+/// introduce tags.
+pub fn escape_bbcode(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            OPEN => escaped.push_str("&#91;"),
+            CLOSE => escaped.push_str("&#93;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Escape bbcode special characters in any expression implementing `AsRef<str>`, see
+/// [escape_bbcode].
+///
+/// ```
+/// use demo_parser::bbcode_escape;
+///
+/// let username = String::from("[b]name[/b]");
+/// assert_eq!(bbcode_escape!(username), "&#91;b&#93;name&#91;/b&#93;");
+/// assert_eq!(bbcode_escape!("[url]"), "&#91;url&#93;");
+/// ```
+#[macro_export]
+macro_rules! bbcode_escape {
+    ($text:expr) => {
+        $crate::escape_bbcode(::std::convert::AsRef::<str>::as_ref(&$text))
+    };
+}
+
+/// Extension methods on text to work with bbcode.
+pub trait BBCodeExt {
+    /// Escape bbcode special characters, see [escape_bbcode].
+    fn bbcode_escape(&self) -> String;
+}
+
+impl BBCodeExt for str {
+    fn bbcode_escape(&self) -> String {
+        escape_bbcode(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::DEFAULT_MAX_DEPTH;
+
+    #[test]
+    fn test_parse_bbcode() {
+        let nodes = parse_bbcode("a[url=https://tsdm39.com]b[b]c[/b][/url]d").unwrap();
+        assert_eq!(
+            nodes,
+            vec![
+                ParsedNode::Text(String::from("a")),
+                ParsedNode::Element {
+                    name: String::from("url"),
+                    attr: Some(String::from("https://tsdm39.com")),
+                    children: vec![
+                        ParsedNode::Text(String::from("b")),
+                        ParsedNode::Element {
+                            name: String::from("b"),
+                            attr: None,
+                            children: vec![ParsedNode::Text(String::from("c"))],
+                        },
+                    ],
+                },
+                ParsedNode::Text(String::from("d")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_bbcode_empty_input() {
+        assert_eq!(parse_bbcode(""), Err(ParseError::EmptyInput));
+    }
+
+    #[test]
+    fn test_parse_bbcode_case_insensitive() {
+        assert_eq!(
+            parse_bbcode("[B]a[/b]").unwrap(),
+            vec![ParsedNode::Element {
+                name: String::from("B"),
+                attr: None,
+                children: vec![ParsedNode::Text(String::from("a"))],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_bbcode_unmatched_tags() {
+        let strict = || ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_bbcode_with_options("[b]a", strict()),
+            Err(ParseError::UnclosedTag {
+                name: String::from("b"),
+                position: 0,
+                location: Some((1, 1)),
+            })
+        );
+        let err = parse_bbcode_with_options("[b]a\n[/i][/b]", strict()).unwrap_err();
+        assert_eq!(
+            err,
+            ParseError::UnexpectedClosingTag {
+                name: String::from("i"),
+                position: 2,
+                location: Some((2, 1)),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "unexpected closing tag \"i\" at line 2 column 1"
+        );
+    }
+
+    #[test]
+    fn test_parse_bbcode_lex_error() {
+        let options = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        let err = parse_bbcode_with_options("[b]a\n[c[/b]", options).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::LexError {
+                position: 2,
+                location: Some((2, 1)),
+                ..
+            }
+        ));
+        assert!(err.to_string().ends_with("at line 2 column 1"));
+        assert_eq!(
+            parse_bbcode("a[c").unwrap(),
+            vec![
+                ParsedNode::Text(String::from("a")),
+                ParsedNode::Text(String::from("[c")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_bbcode_unmatched_tags_non_strict() {
+        assert_eq!(
+            parse_bbcode("[b][i=1]a[/b][/u]").unwrap(),
+            vec![
+                ParsedNode::Element {
+                    name: String::from("b"),
+                    attr: None,
+                    children: vec![
+                        ParsedNode::Text(String::from("[i=1]")),
+                        ParsedNode::Text(String::from("a")),
+                    ],
+                },
+                ParsedNode::Text(String::from("[/u]")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_bbcode_with_options() {
+        let options = ParseOptions {
+            allow_unknown_tags: false,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_bbcode_with_options("[b][foo]a[/foo][/b]", options),
+            Err(ParseError::UnknownTag {
+                name: String::from("foo"),
+                position: 1,
+                location: Some((1, 4)),
+            })
+        );
+
+        let options = ParseOptions {
+            case_sensitive: true,
+            strict: true,
+            ..ParseOptions::default()
+        };
+        assert!(parse_bbcode_with_options("[B]a[/b]", options).is_err());
+
+        let options = ParseOptions {
+            max_depth: Some(1),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parse_bbcode_with_options("[b][i]a[/i][/b]", options),
+            Err(ParseError::TooDeep {
+                max_depth: 1,
+                position: 1,
+                location: Some((1, 4)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_deeply_nested() {
+        let depth = DEFAULT_MAX_DEPTH;
+        let input = format!("{}a{}", "[b]".repeat(depth), "[/b]".repeat(depth));
+        assert_eq!(parse_bbcode(input.as_str()).unwrap().len(), 1);
+        assert_eq!(sanitize_bbcode(input.as_str(), &["i"]), "a");
+
+        let depth = 100_000;
+        let input = format!("{}a{}", "[b]".repeat(depth), "[/b]".repeat(depth));
+        assert!(matches!(
+            parse_bbcode(input.as_str()),
+            Err(ParseError::TooDeep {
+                max_depth: DEFAULT_MAX_DEPTH,
+                ..
+            })
+        ));
+        assert_eq!(sanitize_bbcode(input.as_str(), &["b"]), "");
+        assert_eq!(sanitize_bbcode(input.as_str(), &["i"]), "a");
+        assert_eq!(strip_bbcode(input.as_str()), "a");
+
+        let options = ParseOptions {
+            max_depth: None,
+            ..ParseOptions::default()
+        };
+        let nodes = parse_bbcode_with_options("[b]".repeat(depth).as_str(), options).unwrap();
+        assert_eq!(nodes.len(), depth);
+    }
+
+    #[test]
+    fn test_strip_bbcode() {
+        assert_eq!(
+            strip_bbcode("a[url=https://tsdm39.com]b[b]c[/b][/url]d[/i]"),
+            "abcd"
+        );
+        assert_eq!(strip_bbcode("no tags"), "no tags");
+        assert_eq!(strip_bbcode("[b]a[/b] [c"), "a [c");
+        assert_eq!(
+            strip_bbcode("[b]bold[/b] and [i]x[/i] a [ b"),
+            "bold and x a [ b"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_bbcode() {
+        assert_eq!(
+            sanitize_bbcode(
+                "[B]a[url=https://tsdm39.com]b[i]c[/i][/url][/B]",
+                &["b", "i"]
+            ),
+            "[B]ab[i]c[/i][/B]"
+        );
+        assert_eq!(sanitize_bbcode("", &["b"]), "");
+    }
+
+    #[test]
+    fn test_sanitize_bbcode_unclosed_tags() {
+        assert_eq!(sanitize_bbcode("[url=http://evil]click", &["b"]), "click");
+        assert_eq!(
+            sanitize_bbcode("[img]http://evil/x.png", &["b"]),
+            "http://evil/x.png"
+        );
+        assert_eq!(
+            sanitize_bbcode("[b]a[img]http://evil/x.png[/b][/url]", &["b"]),
+            "[b]ahttp://evil/x.png[/b]"
+        );
+        assert_eq!(sanitize_bbcode("[b]a", &["b"]), "[b]a");
+    }
+
+    #[test]
+    fn test_sanitize_bbcode_joined_tags() {
+        for input in [
+            "[ur[x]l=http://evil]click[/ur[x]l]",
+            "[[x]img]http://evil/x.png[[x]/img]",
+        ] {
+            let output = sanitize_bbcode(input, &["b"]);
+            assert!(
+                parse_bbcode(output.as_str())
+                    .unwrap()
+                    .iter()
+                    .all(|x| matches!(x, ParsedNode::Text(..))),
+                "{input} sanitized into {output}"
+            );
+        }
+        assert_eq!(
+            sanitize_bbcode("[ur[x]l=http://evil]click[/ur[x]l]", &["b"]),
+            "&#91;url=http://evil&#93;click&#91;/url&#93;"
+        );
+    }
+
+    #[test]
+    fn test_escape_bbcode() {
+        assert_eq!(escape_bbcode("[b]a[/b]"), "&#91;b&#93;a&#91;/b&#93;");
+        assert_eq!(escape_bbcode("no tags"), "no tags");
+        assert_eq!(String::from("[[]").bbcode_escape(), "&#91;&#91;&#93;");
+
+        for input in [
+            "[b]x[/b]",
+            "[[b]]x[[/b]]",
+            "[url=http://evil]click[/url]",
+            "[img]a",
+        ] {
+            let nodes = parse_bbcode(escape_bbcode(input)).unwrap();
+            assert!(
+                nodes.iter().all(|x| matches!(x, ParsedNode::Text(..))),
+                "tag found in escaped \"{input}\": {nodes:?}"
+            );
+            assert_eq!(
+                strip_bbcode(escape_bbcode(input).as_str()),
+                escape_bbcode(input)
+            );
+        }
+    }
+}
@@ -1,11 +1,300 @@
-use crate::token::Token;
+use crate::error::ParseError;
+use crate::tag::KNOWN_TAGS;
+use crate::token::{TagHead, TagTail, Token};
+use crate::{CLOSE, EQUAL, OPEN, SLASH};
 
+/// Node in the parsed bbcode tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParsedNode {
+    /// A bbcode tag with its children.
+    ///
+    /// `[$name=$attr]$children[/$name]` or `[$name]$children[/$name]`
+    Element {
+        /// Tag name.
+        name: String,
+
+        /// Optional attribute.
+        attr: Option<String>,
+
+        /// Children nodes.
+        children: Vec<ParsedNode>,
+    },
+
+    /// Plain text.
+    Text(String),
+}
+
+impl ParsedNode {
+    /// Convert back into bbcode text.
+    pub fn to_bbcode(&self) -> String {
+        match self {
+            ParsedNode::Element {
+                name,
+                attr,
+                children,
+            } => {
+                let head = match attr {
+                    Some(attr) => format!("{OPEN}{name}{EQUAL}{attr}{CLOSE}"),
+                    None => format!("{OPEN}{name}{CLOSE}"),
+                };
+                let children = children.iter().map(|x| x.to_bbcode()).collect::<String>();
+                format!("{head}{children}{OPEN}{SLASH}{name}{CLOSE}")
+            }
+            ParsedNode::Text(text) => text.clone(),
+        }
+    }
+}
+
+/// Default max nesting depth of tags in [ParseOptions].
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// Options to configure parsing behavior.
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    /// Fail on unmatched tags and invalid input.
+    ///
+    /// If `false`, unclosed tag heads, unexpected tag tails and invalid input are kept as plain
+    /// text.
+    pub strict: bool,
+
+    /// Accept tags not in [KNOWN_TAGS].
+    ///
+    /// If `false`, unknown tags produce [ParseError::UnknownTag].
+    pub allow_unknown_tags: bool,
+
+    /// Match tag names in head and tail case-sensitively.
+    ///
+    /// If `false`, `[B]text[/b]` is valid.
+    pub case_sensitive: bool,
+
+    /// Max nesting depth of tags, `None` for no limitation.
+    ///
+    /// Default to [DEFAULT_MAX_DEPTH]. Parsing itself does not recurse, but dropping or converting
+    /// the parsed tree does, keep it limited on untrusted input.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            allow_unknown_tags: true,
+            case_sensitive: false,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+        }
+    }
+}
+
+/// Tag opened but not closed yet during parsing.
+struct OpenTag {
+    /// Tag head.
+    head: TagHead,
+
+    /// Position of the head token.
+    position: usize,
+
+    /// Children parsed so far.
+    children: Vec<ParsedNode>,
+}
+
+/// Parser on bbcode tokens.
+///
+/// Nested tags are tracked on an explicit stack instead of recursion, so deeply nested input can
+/// not overflow the call stack.
 pub struct Parser {
+    /// Tokens to parse.
     tokens: Vec<Token>,
+
+    /// Index of the token to parse next.
+    pos: usize,
+
+    /// Parsing options.
+    options: ParseOptions,
+
+    /// Tags opened but not closed yet, the innermost one is the last.
+    open_tags: Vec<OpenTag>,
+
+    /// Line and column number of each token in `tokens`, if known.
+    locations: Vec<(usize, usize)>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens }
+        Self::with_options(tokens, ParseOptions::default())
+    }
+
+    pub fn with_options(tokens: Vec<Token>, options: ParseOptions) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            options,
+            open_tags: vec![],
+            locations: vec![],
+        }
+    }
+
+    /// Set line and column number of each token, used to locate errors.
+    ///
+    /// `locations` shall be in the same order as tokens, see [crate::lexer::Lexer::into_tokens_with_locations].
+    pub fn with_locations(mut self, locations: Vec<(usize, usize)>) -> Self {
+        self.locations = locations;
+        self
     }
+
+    /// Run the process.
+    pub fn parse(&mut self) -> Result<Vec<ParsedNode>, ParseError> {
+        if self.tokens.is_empty() {
+            return Err(ParseError::EmptyInput);
+        }
+
+        let mut nodes = vec![];
+
+        while let Some(token) = self.tokens.get(self.pos).cloned() {
+            let position = self.pos;
+            self.pos += 1;
+
+            match token {
+                Token::Text(text) => self.push_node(&mut nodes, ParsedNode::Text(text)),
+                Token::Error(err) => {
+                    if self.options.strict {
+                        return Err(ParseError::LexError {
+                            message: err.message,
+                            text: err.text,
+                            position,
+                            location: self.location(position),
+                        });
+                    }
+                    self.push_node(&mut nodes, ParsedNode::Text(err.text));
+                }
+                Token::Head(head) => {
+                    if !self.options.allow_unknown_tags && !self.is_known_tag(head.name.as_str()) {
+                        return Err(ParseError::UnknownTag {
+                            name: head.name,
+                            position,
+                            location: self.location(position),
+                        });
+                    }
+
+                    if let Some(max_depth) = self.options.max_depth {
+                        if self.open_tags.len() >= max_depth {
+                            return Err(ParseError::TooDeep {
+                                max_depth,
+                                position,
+                                location: self.location(position),
+                            });
+                        }
+                    }
+
+                    self.open_tags.push(OpenTag {
+                        head,
+                        position,
+                        children: vec![],
+                    });
+                }
+                Token::Tail(tail) => {
+                    if let Some(open_tag) = self.open_tags.last() {
+                        if self.is_same_tag(open_tag.head.name.as_str(), tail.name.as_str()) {
+                            let open_tag = self.open_tags.pop().unwrap();
+                            let element = ParsedNode::Element {
+                                name: open_tag.head.name,
+                                attr: open_tag.head.attr,
+                                children: open_tag.children,
+                            };
+                            self.push_node(&mut nodes, element);
+                            continue;
+                        }
+                    }
+
+                    if self.options.strict {
+                        return Err(ParseError::UnexpectedClosingTag {
+                            name: tail.name,
+                            position,
+                            location: self.location(position),
+                        });
+                    }
+
+                    if let Some(idx) = self
+                        .open_tags
+                        .iter()
+                        .rposition(|x| self.is_same_tag(x.head.name.as_str(), tail.name.as_str()))
+                    {
+                        // The tail closes an outer tag, tags inside it are considered as unclosed,
+                        // parse the tail again with the outer one.
+                        self.pos -= 1;
+                        self.close_as_text(&mut nodes, idx + 1);
+                        continue;
+                    }
+
+                    self.push_node(&mut nodes, ParsedNode::Text(tail_to_text(&tail)));
+                }
+            }
+        }
+
+        if self.options.strict {
+            if let Some(open_tag) = self.open_tags.last() {
+                return Err(ParseError::UnclosedTag {
+                    name: open_tag.head.name.clone(),
+                    position: open_tag.position,
+                    location: self.location(open_tag.position),
+                });
+            }
+        }
+
+        self.close_as_text(&mut nodes, 0);
+
+        Ok(nodes)
+    }
+
+    /// Add `node` to the innermost open tag, or to `nodes` on the top level if no tag is open.
+    fn push_node(&mut self, nodes: &mut Vec<ParsedNode>, node: ParsedNode) {
+        match self.open_tags.last_mut() {
+            Some(open_tag) => open_tag.children.push(node),
+            None => nodes.push(node),
+        }
+    }
+
+    /// Close open tags since index `from` as unclosed, keep each head as plain text followed by its
+    /// children.
+    ///
+    /// Only reachable in non-strict mode.
+    fn close_as_text(&mut self, nodes: &mut Vec<ParsedNode>, from: usize) {
+        let unclosed = self.open_tags.split_off(from);
+        let parent = match self.open_tags.last_mut() {
+            Some(open_tag) => &mut open_tag.children,
+            None => nodes,
+        };
+        for open_tag in unclosed {
+            parent.push(ParsedNode::Text(head_to_text(&open_tag.head)));
+            parent.extend(open_tag.children);
+        }
+    }
+
+    fn location(&self, position: usize) -> Option<(usize, usize)> {
+        self.locations.get(position).copied()
+    }
+
+    fn is_same_tag(&self, head_name: &str, tail_name: &str) -> bool {
+        if self.options.case_sensitive {
+            head_name == tail_name
+        } else {
+            head_name.eq_ignore_ascii_case(tail_name)
+        }
+    }
+
+    fn is_known_tag(&self, name: &str) -> bool {
+        KNOWN_TAGS.iter().any(|x| self.is_same_tag(x, name))
+    }
+}
+
+/// Convert tag head back to the original text.
+fn head_to_text(head: &TagHead) -> String {
+    match &head.attr {
+        Some(attr) => format!("{OPEN}{}{EQUAL}{attr}{CLOSE}", head.name),
+        None => format!("{OPEN}{}{CLOSE}", head.name),
+    }
+}
+
+/// Convert tag tail back to the original text.
+fn tail_to_text(tail: &TagTail) -> String {
+    format!("{OPEN}{SLASH}{}{CLOSE}", tail.name)
 }
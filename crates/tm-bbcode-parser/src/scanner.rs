@@ -3,7 +3,7 @@ use std::str::Chars;
 /// Scanner on a string.
 ///
 /// Provide convienient APIs on operation.
-pub(crate) struct Scanner {
+pub struct Scanner {
     /// characters splitted.
     chars: Vec<char>,
 
@@ -12,17 +12,28 @@ pub(crate) struct Scanner {
 
     /// Current position.
     position: usize,
+
+    /// Positions of the first character in each line.
+    line_starts: Vec<usize>,
 }
 
 impl Scanner {
-    pub(crate) fn new(source: Chars<'_>) -> Scanner {
+    pub fn new(source: Chars<'_>) -> Scanner {
         let chars = source.collect::<Vec<char>>();
-        chars.iter().next();
         let chars_count = chars.len();
+        let line_starts = std::iter::once(0)
+            .chain(
+                chars
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, ch)| (*ch == '\n').then_some(idx + 1)),
+            )
+            .collect();
         Self {
             chars,
             chars_count,
             position: 0,
+            line_starts,
         }
     }
 
@@ -48,6 +59,7 @@ impl Scanner {
     /// Move the position forward and return the character walked through.
     ///
     /// Return `None` if already finished.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<char> {
         if self.done() {
             return None;
@@ -77,4 +89,48 @@ impl Scanner {
     pub fn position(&self) -> usize {
         self.position
     }
+
+    /// Save current position, use [Scanner::restore] to go back to it later.
+    pub fn save(&self) -> usize {
+        self.position
+    }
+
+    /// Go back to position `pos` saved by [Scanner::save].
+    ///
+    /// Positions beyond the end are clamped to the end.
+    pub fn restore(&mut self, pos: usize) {
+        self.position = pos.min(self.chars_count);
+    }
+
+    /// Get the character at `offset` after current position, without advancing the current
+    /// position.
+    ///
+    /// `peek_n(0)` is the same as [Scanner::curr] and `peek_n(1)` is the same as [Scanner::peek].
+    pub fn peek_n(&self, offset: usize) -> Option<&char> {
+        if self.done() {
+            return None;
+        }
+
+        self.chars.get(self.position + offset)
+    }
+
+    /// 1-based line number of the current position.
+    pub fn line(&self) -> usize {
+        self.location(self.position).0
+    }
+
+    /// 1-based column number of the current position.
+    pub fn column(&self) -> usize {
+        self.location(self.position).1
+    }
+
+    /// 1-based line and column number of position `pos`.
+    ///
+    /// Lines are split by `\n`, computed from the whole source so it stays correct after
+    /// [Scanner::back] and [Scanner::restore].
+    pub fn location(&self, pos: usize) -> (usize, usize) {
+        // The first line always starts at 0 so the index is at least 1.
+        let line = self.line_starts.partition_point(|x| *x <= pos);
+        (line, pos - self.line_starts[line - 1] + 1)
+    }
 }
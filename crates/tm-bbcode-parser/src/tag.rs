@@ -1,3 +1,44 @@
+/// Names of bbcode tags supported by the forum.
+pub const KNOWN_TAGS: &[&str] = &[
+    "b",
+    "i",
+    "u",
+    "s",
+    "strike",
+    "sup",
+    "sub",
+    "color",
+    "backcolor",
+    "size",
+    "font",
+    "align",
+    "left",
+    "center",
+    "right",
+    "justify",
+    "p",
+    "indent",
+    "float",
+    "url",
+    "email",
+    "anchor",
+    "img",
+    "media",
+    "audio",
+    "flash",
+    "quote",
+    "code",
+    "collapse",
+    "spoiler",
+    "hide",
+    "free",
+    "list",
+    "table",
+    "tr",
+    "td",
+    "hr",
+];
+
 pub trait Tag {
     /// Convert into BBCode.
     fn to_bbcode(&self) -> String;
@@ -1,8 +1,8 @@
 /// All types of tokens.
 ///
 /// Tokens are units of of bbcode tags.
-#[derive(Debug)]
-pub(crate) enum Token {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
     /// Tag head.
     Head(TagHead),
 
@@ -11,13 +11,16 @@ pub(crate) enum Token {
 
     /// Plain text.
     Text(String),
+
+    /// Invalid input.
+    Error(LexError),
 }
 
 /// Tag head.
 ///
 /// `[$name=$attr]` or `[$name]`
-#[derive(Debug)]
-pub(crate) struct TagHead {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagHead {
     /// Tag name.
     pub name: String,
 
@@ -28,8 +31,22 @@ pub(crate) struct TagHead {
 /// Tag tail.
 ///
 /// `[/$name]`
-#[derive(Debug)]
-pub(crate) struct TagTail {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagTail {
     /// Tag name.
     pub name: String,
 }
+
+/// Error on invalid input found by lexer.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{message} at character {position}")]
+pub struct LexError {
+    /// Describe the error.
+    pub message: String,
+
+    /// Position of the first character of the invalid input.
+    pub position: usize,
+
+    /// The invalid input text.
+    pub text: String,
+}
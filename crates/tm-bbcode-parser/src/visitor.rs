@@ -0,0 +1,68 @@
+use crate::parser::ParsedNode;
+
+/// Visitor on the parsed bbcode tree.
+///
+/// Use [walk] to traverse the tree.
+pub trait BBCodeVisitor {
+    /// Visit an element node.
+    ///
+    /// Called before the `children` are visited.
+    fn visit_element(&mut self, name: &str, attr: Option<&str>, children: &[ParsedNode]);
+
+    /// Visit a text node.
+    fn visit_text(&mut self, text: &str);
+}
+
+/// Traverse `nodes` in pre-order, call `visitor` on each node.
+pub fn walk(nodes: &[ParsedNode], visitor: &mut dyn BBCodeVisitor) {
+    for node in nodes {
+        match node {
+            ParsedNode::Element {
+                name,
+                attr,
+                children,
+            } => {
+                visitor.visit_element(name.as_str(), attr.as_deref(), children.as_slice());
+                walk(children.as_slice(), visitor);
+            }
+            ParsedNode::Text(text) => visitor.visit_text(text.as_str()),
+        }
+    }
+}
+
+/// Visitor collects all text in the tree.
+///
+/// ```
+/// use demo_parser::parse_bbcode;
+/// use demo_parser::visitor::{walk, PlainTextExtractor};
+///
+/// let nodes = parse_bbcode("[b]bold[/b] and [i]italic[/i]").unwrap();
+/// let mut extractor = PlainTextExtractor::default();
+/// walk(nodes.as_slice(), &mut extractor);
+/// assert_eq!(extractor.text(), "bold and italic");
+/// ```
+#[derive(Debug, Default)]
+pub struct PlainTextExtractor {
+    /// All text nodes visited, in order.
+    texts: Vec<String>,
+}
+
+impl PlainTextExtractor {
+    /// All text nodes visited, in order.
+    pub fn texts(&self) -> &[String] {
+        self.texts.as_slice()
+    }
+
+    /// Concatenated text.
+    pub fn text(&self) -> String {
+        self.texts.concat()
+    }
+}
+
+impl BBCodeVisitor for PlainTextExtractor {
+    fn visit_element(&mut self, _name: &str, _attr: Option<&str>, _children: &[ParsedNode]) {}
+
+    fn visit_text(&mut self, text: &str) {
+        self.texts.push(text.to_string());
+    }
+}
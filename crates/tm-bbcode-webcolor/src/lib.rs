@@ -1,11 +1,19 @@
 use racros::AutoStr;
 
+/// Error returned when constructing a [`WebColor`] from invalid input.
+#[derive(Clone, Debug, thiserror::Error)]
+#[error("invalid web color: {message}")]
+pub struct ColorParseError {
+    /// Describes why the input is rejected.
+    pub message: String,
+}
+
 /// Web colors supported in bbcode.
 ///
 /// There are 40 kinds of pre-defined colors available.
 ///
 /// And a custom color [`WebColors::Custom`] which represent its value in string.
-#[derive(AutoStr)]
+#[derive(AutoStr, Clone, Debug, PartialEq, Eq, Hash)]
 #[autorule = "PascalCase"]
 pub enum WebColor {
     Black,
@@ -61,3 +69,77 @@ pub enum WebColor {
     /// constraints, use any format considered as available.
     Custom(String),
 }
+
+impl WebColor {
+    /// Build a [`WebColor::Custom`] from hex color text.
+    ///
+    /// Accepted formats are `#RRGGBB` and `#RRGGBBAA`.
+    ///
+    /// ```rust
+    /// use tm_bbcode_webcolor::WebColor;
+    ///
+    /// assert_eq!(WebColor::from_hex("#cc0000").unwrap().to_string(), "#cc0000");
+    /// assert!(WebColor::from_hex("cc0000").is_err());
+    /// assert!(WebColor::from_hex("#cc00").is_err());
+    /// ```
+    pub fn from_hex(s: &str) -> Result<WebColor, ColorParseError> {
+        let digits = match s.strip_prefix('#') {
+            Some(v) => v,
+            None => {
+                return Err(ColorParseError {
+                    message: format!("hex color \"{s}\" shall start with '#'"),
+                })
+            }
+        };
+
+        if digits.len() != 6 && digits.len() != 8 {
+            return Err(ColorParseError {
+                message: format!("hex color \"{s}\" shall be in #RRGGBB or #RRGGBBAA format"),
+            });
+        }
+
+        if !digits.chars().all(|x| x.is_ascii_hexdigit()) {
+            return Err(ColorParseError {
+                message: format!("hex color \"{s}\" contains non-hex digit"),
+            });
+        }
+
+        Ok(WebColor::Custom(s.to_string()))
+    }
+
+    /// Build a [`WebColor::Custom`] in `rgb(r, g, b)` format.
+    ///
+    /// ```rust
+    /// use tm_bbcode_webcolor::WebColor;
+    ///
+    /// assert_eq!(WebColor::from_rgb(255, 0, 0).to_string(), "rgb(255, 0, 0)");
+    /// ```
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> WebColor {
+        WebColor::Custom(format!("rgb({r}, {g}, {b})"))
+    }
+
+    /// Return the color in css format.
+    ///
+    /// Pre-defined colors are converted into lowercase css color names, [`WebColor::Custom`]
+    /// returns its raw value.
+    ///
+    /// ```rust
+    /// use tm_bbcode_webcolor::WebColor;
+    ///
+    /// assert_eq!(WebColor::DarkRed.to_css_string(), "darkred");
+    /// assert_eq!(WebColor::from_rgb(1, 2, 3).to_css_string(), "rgb(1, 2, 3)");
+    /// ```
+    pub fn to_css_string(&self) -> String {
+        match self {
+            WebColor::Custom(v) => v.clone(),
+            v => v.to_string().to_lowercase(),
+        }
+    }
+
+    /// Check is one of the pre-defined colors or not.
+    ///
+    /// Only [`WebColor::Custom`] is not named.
+    pub fn is_named(&self) -> bool {
+        !matches!(self, WebColor::Custom(..))
+    }
+}
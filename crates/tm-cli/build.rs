@@ -0,0 +1,43 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Record build info for the version subcommand.
+fn main() {
+    let build_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=TM_CLI_BUILD_TIMESTAMP={build_timestamp}");
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Git is optional, e.g. building from a source tarball.
+    if let Some(hash) = git(&["rev-parse", "--short", "HEAD"]) {
+        println!("cargo:rustc-env=TM_CLI_GIT_HASH={hash}");
+    }
+
+    // Rebuild when HEAD moves to another commit, either by checkout or by committing on the
+    // current branch.
+    // Only watch existing files, cargo reruns the build script every time on missing ones.
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        let mut watched = vec![format!("{git_dir}/HEAD")];
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            watched.push(format!("{git_dir}/{head_ref}"));
+            watched.push(format!("{git_dir}/packed-refs"));
+        }
+        for path in watched.iter().filter(|x| Path::new(x).exists()) {
+            println!("cargo:rerun-if-changed={path}");
+        }
+    }
+}
+
+/// Run git with `args`, return the trimmed stdout if succeeded.
+fn git(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|x| x.status.success())
+        .and_then(|x| String::from_utf8(x.stdout).ok())
+        .map(|x| x.trim().to_string())
+}
@@ -1,13 +1,22 @@
 use crate::cmd::AnalyzeArgs;
-use crate::config::{Config, LoadedThreadPage, Participation, RewardPolicy, Round, DUPLICATE_INFO};
-use crate::utils::{load_thread_data_from_dir, ThreadPageData};
+use crate::config::{
+    join_validation_errors, AnalyzeBuckets, Config, LoadedThreadPage, Participation, Reward,
+    RewardPolicy, Round, DUPLICATE_INFO,
+};
+use crate::utils::{
+    green, load_thread_data_from_dir, progress_bar, red, sort_by_page, validate_continuity, yellow,
+    ThreadPageData,
+};
 use anyhow::{bail, Context, Result};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
 use std::cmp::Ordering;
 use std::fs::OpenOptions;
 use std::io::{BufWriter, Write};
 use tm_api::post::generate_find_post_link;
 use tm_bbcode_macro::bbx;
 use tm_bbcode_webcolor::WebColor;
+use tm_types::BASE_URL;
 use tokio::fs;
 use tracing::trace;
 
@@ -15,7 +24,7 @@ const TABLE_WIDTH_30: usize = 30;
 const TABLE_WIDTH_110: usize = 110;
 
 /// Participation status on a user.
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct UserParticipation {
     /// User's username.
     pub username: String,
@@ -47,6 +56,24 @@ impl UserParticipation {
         }
     }
 
+    /// Count of all rounds.
+    ///
+    /// Zero if the registration is duplicate.
+    pub(crate) fn total_rounds(&self) -> usize {
+        self.rounds.as_ref().map(|v| v.len()).unwrap_or(0)
+    }
+
+    /// Ratio of completely participated rounds in all rounds, in range `0.0..=1.0`.
+    ///
+    /// Zero if no round.
+    pub(crate) fn participation_rate(&self) -> f64 {
+        let total = self.total_rounds();
+        if total == 0 {
+            return 0.0;
+        }
+        (total - self.count_missing_rounds()) as f64 / total as f64
+    }
+
     /// Generate rounds info text.
     pub(crate) fn missed_info(&self, indent: usize) -> String {
         match &self.rounds {
@@ -66,10 +93,13 @@ impl UserParticipation {
     pub(crate) fn generate_csv_record(
         &self,
         reward_policy: &RewardPolicy,
+        buckets: &AnalyzeBuckets,
         signature_verified_floors: Option<&Vec<usize>>,
     ) -> Vec<String> {
-        let missed_count = self.count_missing_rounds();
-        let pat = match missed_count {
+        let missing_rounds = self.count_missing_rounds();
+        let bucket = buckets.bucket(missing_rounds);
+        // Label by the actual count, buckets may hold different counts of missing rounds.
+        let pat = match missing_rounds {
             0 => "全过程",
             1 => "少一轮",
             2 => "少两轮",
@@ -82,7 +112,7 @@ impl UserParticipation {
             self.username.clone(),
             self.uid.to_string(),
             pat.to_string(),
-            reward_policy.generate_reward_text(missed_count),
+            reward_policy.generate_reward_text(bucket),
             if signature_verified_floors
                 .map(|x| x.contains(&self.floor))
                 .unwrap_or(false)
@@ -95,6 +125,64 @@ impl UserParticipation {
         ]
     }
 
+    /// Generate detailed participation info on every thread in every round, with post links.
+    pub(crate) fn detail_info(&self) -> String {
+        let mut result = format!(
+            "{}({} #{}) {BASE_URL}/{}\n",
+            self.username,
+            self.uid,
+            self.floor,
+            generate_find_post_link(self.reg_pid.as_str())
+        );
+        let rounds = match &self.rounds {
+            Some(v) => v,
+            None => {
+                result.push_str(format!("  {DUPLICATE_INFO}\n").as_str());
+                return result;
+            }
+        };
+
+        for round in rounds.iter() {
+            let state = if round.is_missed() { "missed" } else { "ok" };
+            result.push_str(format!("  {} ({state})\n", round.name).as_str());
+            for group in round.group.iter() {
+                let indent = match group.name.as_ref() {
+                    Some(name) if !name.is_empty() => {
+                        result.push_str(format!("    {name}\n").as_str());
+                        6
+                    }
+                    _ => 4,
+                };
+                for thread in group.thread.iter() {
+                    let post = match thread.state {
+                        Participation::Missed => String::new(),
+                        Participation::Ok | Participation::Invalid => format!(
+                            " #{} {BASE_URL}/{}",
+                            thread.floor,
+                            generate_find_post_link(thread.pid.as_str())
+                        ),
+                    };
+                    let reason = thread
+                        .invalid_reason
+                        .as_ref()
+                        .map(|x| format!(" ({x})"))
+                        .unwrap_or_default();
+                    result.push_str(
+                        format!(
+                            "{}{}: {:?}{reason}{post}\n",
+                            " ".repeat(indent),
+                            thread.name,
+                            thread.state
+                        )
+                        .as_str(),
+                    );
+                }
+            }
+        }
+
+        result
+    }
+
     pub(crate) fn generate_bbcode(&self) -> String {
         if self.rounds.is_none() {
             return bbx!(
@@ -124,6 +212,9 @@ impl UserParticipation {
 /// Produced result on user participation.
 ///
 /// User participation result grouped by missing rounds count.
+///
+/// Serialized as a map from [AnalyzeBuckets::key] to users in that bucket, only buckets in use are
+/// included.
 #[derive(Debug)]
 struct AnalyzeResult {
     /// Users participated in all threads of all rounds.
@@ -140,16 +231,60 @@ struct AnalyzeResult {
 
     /// Users participated in four fewer rounds.
     missing4: Vec<UserParticipation>,
+
+    /// How users are grouped into the fields above, in order.
+    buckets: AnalyzeBuckets,
+}
+
+impl Serialize for AnalyzeResult {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.buckets.len()))?;
+        for (idx, users) in self
+            .buckets()
+            .into_iter()
+            .take(self.buckets.len())
+            .enumerate()
+        {
+            map.serialize_entry(self.buckets.key(idx).as_str(), users)?;
+        }
+        map.end()
+    }
 }
 
 impl AnalyzeResult {
-    fn new() -> Self {
+    fn new(buckets: AnalyzeBuckets) -> Self {
         AnalyzeResult {
             complete: vec![],
             missing1: vec![],
             missing2: vec![],
             missing3: vec![],
             missing4: vec![],
+            buckets,
+        }
+    }
+
+    /// All buckets of users in order.
+    fn buckets(&self) -> [&Vec<UserParticipation>; AnalyzeBuckets::MAX_BUCKETS] {
+        [
+            &self.complete,
+            &self.missing1,
+            &self.missing2,
+            &self.missing3,
+            &self.missing4,
+        ]
+    }
+
+    /// Mutable reference to users in bucket `idx`.
+    fn bucket_mut(&mut self, idx: usize) -> &mut Vec<UserParticipation> {
+        match idx {
+            0 => &mut self.complete,
+            1 => &mut self.missing1,
+            2 => &mut self.missing2,
+            3 => &mut self.missing3,
+            _ => &mut self.missing4,
         }
     }
 
@@ -170,90 +305,61 @@ impl AnalyzeResult {
         data
     }
 
+    /// Sum of rewards applied on all users according to `reward_policy`.
+    fn total_reward(&self, reward_policy: &RewardPolicy) -> Reward {
+        let mut total = Reward::default();
+        for p in self.combine_and_sort() {
+            let bucket = self.buckets.bucket(p.count_missing_rounds());
+            total += reward_policy.reward(bucket).clone();
+        }
+        total
+    }
+
+    /// Average participation rate of all users, excluding duplicate registrations.
+    ///
+    /// Return `None` if no user.
+    fn average_participation_rate(&self) -> Option<f64> {
+        let rates = self
+            .combine_and_sort()
+            .into_iter()
+            .filter(|x| x.total_rounds() > 0)
+            .map(|x| x.participation_rate())
+            .collect::<Vec<_>>();
+        if rates.is_empty() {
+            return None;
+        }
+        Some(rates.iter().sum::<f64>() / rates.len() as f64)
+    }
+
     fn generate_text_result(&self) -> String {
         let mut result = String::new();
 
-        let complete_count = self.complete.len();
-        let missing1_count = self.missing1.len();
-        let missing2_count = self.missing2.len();
-        let missing3_count = self.missing3.len();
-        let missing4_count = self.missing4.len();
-
         result.push_str(
             format!(
                 "Total users: {}\n",
-                complete_count + missing1_count + missing2_count + missing3_count + missing4_count
+                self.buckets().iter().map(|x| x.len()).sum::<usize>()
             )
             .as_str(),
         );
-        if complete_count > 0 {
-            result.push_str(format!("Users complete all rounds: {complete_count}\n").as_str());
-            for p in self.complete.iter() {
-                result.push_str(format!("  {}({} #{})\n", p.username, p.uid, p.floor).as_str());
-            }
+        if let Some(rate) = self.average_participation_rate() {
+            result.push_str(format!("Average participation rate: {:.2}%\n", rate * 100.0).as_str());
         }
 
-        if missing1_count > 0 {
-            result.push_str(format!("Users missing 1 round: {missing1_count}\n").as_str());
-            for p in self.missing1.iter() {
-                result.push_str(
-                    format!(
-                        "  {}({} #{})\n{}\n",
-                        p.username,
-                        p.uid,
-                        p.floor,
-                        p.missed_info(4)
-                    )
-                    .as_str(),
-                );
-            }
-        }
-
-        if missing2_count > 0 {
-            result.push_str(format!("Users missing 2 rounds: {missing2_count}\n").as_str());
-            for p in self.missing2.iter() {
-                result.push_str(
-                    format!(
-                        "  {}({} #{})\n{}\n",
-                        p.username,
-                        p.uid,
-                        p.floor,
-                        p.missed_info(4)
-                    )
-                    .as_str(),
-                );
+        for (idx, users) in self.buckets().into_iter().enumerate() {
+            if idx >= self.buckets.len() || users.is_empty() {
+                continue;
             }
-        }
-
-        if missing3_count > 0 {
-            result.push_str(format!("Users missing 3 rounds: {missing3_count}\n").as_str());
-            for p in self.missing3.iter() {
-                result.push_str(
-                    format!(
-                        "  {}({} #{})\n{}\n",
-                        p.username,
-                        p.uid,
-                        p.floor,
-                        p.missed_info(4)
-                    )
-                    .as_str(),
-                );
+            let title = format!("Users {}: {}\n", self.buckets.describe(idx), users.len());
+            if self.buckets.thresholds[idx] == 0 {
+                result.push_str(green(title).as_str());
+            } else {
+                result.push_str(yellow(title).as_str());
             }
-        }
-
-        if missing4_count > 0 {
-            result.push_str(format!("Users missing 4 or more rounds: {missing4_count}\n").as_str());
-            for p in self.missing4.iter() {
-                result.push_str(
-                    format!(
-                        "  {}({} #{})\n{}\n",
-                        p.username,
-                        p.uid,
-                        p.floor,
-                        p.missed_info(4)
-                    )
-                    .as_str(),
-                );
+            for p in users.iter() {
+                result.push_str(format!("  {}({} #{})\n", p.username, p.uid, p.floor).as_str());
+                if p.count_missing_rounds() > 0 {
+                    result.push_str(format!("{}\n", red(p.missed_info(4))).as_str());
+                }
             }
         }
 
@@ -278,7 +384,13 @@ impl AnalyzeResult {
     ) -> Vec<Vec<String>> {
         self.combine_and_sort()
             .into_iter()
-            .map(|x| x.generate_csv_record(reward_policy, verified_signature_floors.as_ref()))
+            .map(|x| {
+                x.generate_csv_record(
+                    reward_policy,
+                    &self.buckets,
+                    verified_signature_floors.as_ref(),
+                )
+            })
             .collect()
     }
 
@@ -322,51 +434,63 @@ pub async fn run_analyze_command(args: AnalyzeArgs) -> Result<()> {
         .context("when reading config file")?;
     let config: Config = toml::from_str(data.as_str()).context("invalid config")?;
     trace!("{config:#?}");
-
-    let mut reg_data = load_thread_data_from_dir(config.registration_path.as_str())
-        .await
-        .context("failed to load registration data")?;
-
-    // Filter floors need to skip.
-    // Those floors are the ones in registration thread but used for other usage rather than sign
-    // the registration.
-    if let Some(floors_to_skip) = args.skip_reg_floors {
-        reg_data.iter_mut().for_each(|page| {
-            page.thread
-                .post_list
-                .retain_mut(|post| !floors_to_skip.contains(&post.floor))
-        });
+    config.buckets.validate()?;
+
+    let participation_result = match args.load_json_path {
+        Some(json_path) => {
+            // Use the cached result, skip loading thread data.
+            println!("loading participation result from {json_path}");
+            let data = fs::read_to_string(json_path.as_str())
+                .await
+                .with_context(|| format!("failed to read participation result {json_path}"))?;
+            serde_json::from_str::<Vec<UserParticipation>>(data.as_str())
+                .context("invalid participation result json data")?
+        }
+        None => load_participation_result(&config, &args.skip_reg_floors).await?,
     };
 
-    if reg_data.is_empty() {
-        bail!("error: empty registration data")
+    if let Some(json_path) = args.save_json_path {
+        println!("writing participation result to {json_path}");
+        let data = serde_json::to_string_pretty(&participation_result)
+            .context("failed to serialize participation result")?;
+        fs::write(json_path.as_str(), data)
+            .await
+            .with_context(|| format!("failed to save participation result to {json_path}"))?;
+        println!("participation result saved in {json_path}");
     }
 
-    let post_data = config
-        .load_thread_data(None)
-        .await
-        .context("failed to load thread from config")?;
-
-    println!(
-        "loaded reg_data, post count {}",
-        reg_data
-            .iter()
-            .fold(0, |acc, x| acc + x.thread.post_list.len())
-    );
-    println!(
-        "loaded post_data, post count {}",
-        post_data
-            .iter()
-            .fold(0, |acc, x| acc + x.thread.post_list.len())
-    );
-
-    trace!("producing participation result");
-    let participation_result = produce_participation_result(reg_data, post_data, config.round);
-
     trace!("producing analyze result");
-    let analyze_result = produce_analyze_result(participation_result);
-
-    println!("{}", analyze_result.generate_text_result());
+    let analyze_result = produce_analyze_result(participation_result, config.buckets.clone());
+
+    match args.user {
+        Some(user) => {
+            // Only show the specified user, all users are still analyzed.
+            let users = analyze_result
+                .combine_and_sort()
+                .into_iter()
+                .filter(|x| x.username == user || x.uid == user)
+                .collect::<Vec<_>>();
+            if users.is_empty() {
+                println!("user {user} not found in registration");
+            }
+            for p in users {
+                println!("{}", p.detail_info());
+            }
+        }
+        None => {
+            println!("{}", analyze_result.generate_text_result());
+            let total_reward = analyze_result.total_reward(&config.reward_policy);
+            if total_reward.is_zero() {
+                println!("Total reward: none");
+            } else {
+                println!(
+                    "Total reward: {} ({} points excluding xc)",
+                    total_reward.generate_reward_text(),
+                    total_reward.total_points()
+                );
+            }
+        }
+    }
 
     if let Some(csv_path) = args.save_csv_path {
         let signature_verified_floors: Option<Vec<usize>> =
@@ -409,6 +533,16 @@ pub async fn run_analyze_command(args: AnalyzeArgs) -> Result<()> {
         println!("csv data saved in {csv_path}");
     }
 
+    if let Some(json_path) = args.json_path {
+        println!("writing json analyze result to {json_path}");
+        let data = serde_json::to_string_pretty(&analyze_result)
+            .context("failed to serialize analyze result")?;
+        fs::write(json_path.as_str(), data)
+            .await
+            .with_context(|| format!("failed to save json analyze result to {json_path}"))?;
+        println!("json analyze result saved in {json_path}");
+    }
+
     if let Some(status_path) = args.save_status_path {
         println!("writing participation status data to {status_path}");
         let file = OpenOptions::new()
@@ -428,39 +562,92 @@ pub async fn run_analyze_command(args: AnalyzeArgs) -> Result<()> {
     Ok(())
 }
 
+/// Load registration and thread data specified in `config`, produce participation result of all
+/// users.
+///
+/// Floors in `skip_reg_floors` are excluded from registration.
+async fn load_participation_result(
+    config: &Config,
+    skip_reg_floors: &[usize],
+) -> Result<Vec<UserParticipation>> {
+    let mut reg_data = load_thread_data_from_dir(config.registration_path.as_str())
+        .await
+        .context("failed to load registration data")?;
+    sort_by_page(&mut reg_data);
+    validate_continuity(&reg_data).context("invalid registration data")?;
+
+    // Filter floors need to skip.
+    // Those floors are the ones in registration thread but used for other usage rather than sign
+    // the registration.
+    reg_data.iter_mut().for_each(|page| {
+        page.thread
+            .post_list
+            .retain(|post| !skip_reg_floors.contains(&post.floor))
+    });
+
+    if reg_data.is_empty() {
+        bail!("error: empty registration data")
+    }
+
+    let post_data = config
+        .load_thread_data(None)
+        .await
+        .context("failed to load thread from config")?;
+
+    println!(
+        "loaded reg_data, post count {}",
+        reg_data
+            .iter()
+            .fold(0, |acc, x| acc + x.thread.post_list.len())
+    );
+    println!(
+        "loaded post_data, post count {}",
+        post_data
+            .iter()
+            .fold(0, |acc, x| acc + x.thread.post_list.len())
+    );
+
+    trace!("producing participation result");
+    let participation_result =
+        produce_participation_result(reg_data, post_data, config.round.clone());
+
+    Ok(participation_result)
+}
+
 fn produce_participation_result(
     reg_data: Vec<ThreadPageData>,
     post_data: Vec<LoadedThreadPage>,
     flags_template: Vec<Round>,
 ) -> Vec<UserParticipation> {
     let mut analyze_result = Vec::with_capacity(reg_data.len());
+    let bar = progress_bar(
+        reg_data
+            .iter()
+            .map(|x| x.thread.post_list.len() as u64)
+            .sum(),
+        "analyzing users",
+    );
 
     trace!("traversing registration data");
     for (reg_page_number, reg_page) in reg_data.iter().enumerate() {
         trace!("traversing registration data page={}", reg_page_number);
         // Each reg is a post in the registration thread, where one user registered.
         for reg in reg_page.thread.post_list.iter() {
+            bar.inc(1);
             trace!(
                 "checking registration data floor={}, user={}, uid={}",
                 reg.floor,
                 reg.author,
                 reg.author_id
             );
-            if reg.floor == 1 {
-                trace!("skip the first floor");
-                // Skip the first floor, it is the announcement.
-                continue;
-            }
-
             // Find the current registration's user again, try to find the first occurred one.
             // If the position is not same, then the current registration is a duplicate one.
             let maybe_another_reg = reg_data
                 .iter()
                 .find_map(|page| {
                     page.thread
-                        .post_list
-                        .iter()
-                        .find(|post| post.author_id == reg.author_id)
+                        .posts_by_author(reg.author_id.as_str())
+                        .first()
                         .map(|p| p.floor)
                 })
                 .unwrap();
@@ -470,9 +657,9 @@ fn produce_participation_result(
                 // Traverse finish, produce result for the user.
                 analyze_result.push(UserParticipation {
                     username: reg.author.clone(),
-                    uid: reg.author_id.clone(),
+                    uid: reg.author_id.to_string(),
                     floor: reg.floor.clone(),
-                    reg_pid: reg.id.clone(),
+                    reg_pid: reg.id.to_string(),
                     // A `None` value means duplicate floor.
                     rounds: None,
                 });
@@ -497,7 +684,7 @@ fn produce_participation_result(
                             )
                         }) {
                             Some(post) => {
-                                thread.pid = post.id.clone();
+                                thread.pid = post.id.to_string();
                                 thread.floor = post.floor;
                                 if thread
                                     .revised
@@ -519,11 +706,18 @@ fn produce_participation_result(
                                 {
                                     // Duplicate floor, invalid.
                                     thread.state = Participation::Invalid;
-                                } else if !thread
-                                    .validate_poll_format(post.body.as_str(), post.floor)
+                                    thread.invalid_reason = Some("duplicate poll".to_string());
+                                } else if let Err(errors) =
+                                    thread.validate_poll_format(post.body.as_str())
                                 {
                                     // Incorrect format, invalid.
+                                    let reason = join_validation_errors(&errors);
+                                    println!(
+                                        "invalid poll: thread {} floor {}: {reason}",
+                                        thread.name, post.floor
+                                    );
                                     thread.state = Participation::Invalid;
+                                    thread.invalid_reason = Some(reason);
                                 } else {
                                     thread.state = Participation::Ok;
                                 }
@@ -546,14 +740,15 @@ fn produce_participation_result(
             // Traverse finish, produce result for the user.
             analyze_result.push(UserParticipation {
                 username: reg.author.clone(),
-                uid: reg.author_id.clone(),
+                uid: reg.author_id.to_string(),
                 floor: reg.floor.clone(),
-                reg_pid: reg.id.clone(),
+                reg_pid: reg.id.to_string(),
                 rounds: Some(flags),
             });
         }
     }
 
+    bar.finish_and_clear();
     analyze_result
 }
 
@@ -577,17 +772,15 @@ fn sort_user_participation_ref(lhs: &&UserParticipation, rhs: &&UserParticipatio
     }
 }
 
-fn produce_analyze_result(user_participation: Vec<UserParticipation>) -> AnalyzeResult {
-    let mut analyze_result = AnalyzeResult::new();
+fn produce_analyze_result(
+    user_participation: Vec<UserParticipation>,
+    buckets: AnalyzeBuckets,
+) -> AnalyzeResult {
+    let mut analyze_result = AnalyzeResult::new(buckets);
 
     for p in user_participation.into_iter() {
-        match p.count_missing_rounds() {
-            0 => analyze_result.complete.push(p),
-            1 => analyze_result.missing1.push(p),
-            2 => analyze_result.missing2.push(p),
-            3 => analyze_result.missing3.push(p),
-            4.. => analyze_result.missing4.push(p),
-        }
+        let bucket = analyze_result.buckets.bucket(p.count_missing_rounds());
+        analyze_result.bucket_mut(bucket).push(p);
     }
 
     analyze_result.complete.sort_by(sort_user_participation);
@@ -1,13 +1,18 @@
 use crate::analyze::run_analyze_command;
+use crate::completion::run_completion_command;
 use crate::parse::run_parse_command;
 use crate::points::run_points_command;
 use crate::profile::run_profile_command;
 use crate::signature::run_signature_command;
+use crate::template::run_config_command;
 use crate::thread::run_thread_command;
 use crate::validate::run_validate_command;
+use crate::version::run_version_command;
 use anyhow::Result;
 use clap::{arg, ArgAction};
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use tm_types::{PageNumber, ThreadId};
 ///////// Groups /////////
 
 #[derive(Clone, Debug, Args)]
@@ -26,20 +31,38 @@ pub struct ProfileTargetGroups {
     pub thread_data: Option<String>,
 }
 
+#[derive(Clone, Debug, Args)]
+#[group(required = true, multiple = false)]
+pub struct SignatureTargetGroups {
+    #[arg(
+        long = "tid",
+        help = "check signature has link to any of the threads or not\nComma separated or repeat the flag to specify multiple threads",
+        value_delimiter = ',',
+        num_args = 1..
+    )]
+    pub tids: Vec<String>,
+
+    #[arg(
+        long = "pattern",
+        help = "check signature has link matching the regular expression or not"
+    )]
+    pub pattern: Option<String>,
+}
+
 ///////// Args /////////
 
 #[derive(Clone, Debug, Args)]
 pub struct ThreadArgs {
     #[arg(short = 't', long = "tid", help = "Thread id to fetch")]
-    pub tid: u32,
+    pub tid: ThreadId,
 
     #[arg(
         short = 'p',
         long = "page",
-        help = "Page number to fetch, single number value",
+        help = "Page number to fetch, single number value starts from 1",
         default_value = "1"
     )]
-    pub page: u32,
+    pub page: PageNumber,
 
     #[arg(
         short = 'a',
@@ -50,12 +73,55 @@ pub struct ThreadArgs {
     )]
     pub all: Option<bool>,
 
+    #[arg(
+        long = "from-page",
+        help = "First page to fetch when fetching all pages, starts from 1",
+        default_value = "1"
+    )]
+    pub from_page: PageNumber,
+
+    #[arg(
+        long = "to-page",
+        help = "Last page to fetch when fetching all pages, default to the last page in thread"
+    )]
+    pub to_page: Option<PageNumber>,
+
     #[arg(
         short = 'o',
         long = "output",
         help = "Directory to save fetched content"
     )]
     pub output: Option<String>,
+
+    #[arg(
+        long = "delay",
+        help = "Minimum delay between requests in milliseconds",
+        default_value = "700"
+    )]
+    pub delay: u64,
+
+    #[arg(
+        long = "retry",
+        help = "Times to retry when failed to fetch a page, wait 1 second before each retry",
+        default_value = "0"
+    )]
+    pub retry: u32,
+
+    #[arg(
+        long = "retry-backoff",
+        help = "Double the delay after each retry instead of waiting 1 second every time, up to 30 seconds",
+        default_value = "false",
+        action = ArgAction::SetTrue,
+    )]
+    pub retry_backoff: bool,
+
+    #[arg(
+        long = "concurrent",
+        help = "Count of pages to fetch at the same time when fetching all pages, each has its own rate limit. Values larger than 3 may get banned by the server",
+        default_value = "1",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub concurrent: u32,
 }
 
 #[derive(Clone, Debug, Args)]
@@ -88,13 +154,38 @@ pub struct AnalyzeArgs {
     )]
     pub save_status_path: Option<String>,
 
+    #[arg(
+        long = "json",
+        help = "file to save json format analyze result, for other tools to consume"
+    )]
+    pub json_path: Option<String>,
+
+    #[arg(
+        long = "save-json-path",
+        help = "file to save intermediate participation result, load it later with --load-json-path"
+    )]
+    pub save_json_path: Option<String>,
+
+    #[arg(
+        long = "load-json-path",
+        help = "file to load intermediate participation result saved by --save-json-path, skip loading thread data"
+    )]
+    pub load_json_path: Option<String>,
+
+    #[arg(
+        long = "user",
+        help = "only print detailed participation of the user, specified by username or uid"
+    )]
+    pub user: Option<String>,
+
     #[arg(
         long = "skip-reg-floors",
-        help = "specify floors in registration thread, those shall be excluded from analyzing\ne.g. the first floor or second floor used for other usage rather than registration",
-        value_delimiter = ' ',
-        num_args = 1..
+        help = "specify floors in registration thread, those shall be excluded from analyzing\ne.g. the first floor or second floor used for other usage rather than registration\nComma separated, default to the first floor which is the announcement",
+        value_delimiter = ',',
+        num_args = 1..,
+        default_value = "1"
     )]
-    pub skip_reg_floors: Option<Vec<usize>>,
+    pub skip_reg_floors: Vec<usize>,
 }
 
 #[derive(Clone, Debug, Args)]
@@ -108,6 +199,26 @@ pub struct ProfileArgs {
         help = "Directory to save fetched content"
     )]
     pub output: Option<String>,
+
+    #[arg(
+        long = "export-csv",
+        help = "file to save csv format profile summary, only works with --thread-data"
+    )]
+    pub export_csv: Option<String>,
+
+    #[arg(
+        long = "filter-group",
+        help = "only keep users in the user group, only works with --thread-data. All profiles are still saved. Repeat to keep multiple groups"
+    )]
+    pub filter_group: Vec<String>,
+
+    #[arg(
+        long = "download-avatars",
+        help = "also download user avatars into avatars dir in output dir, only works with --thread-data",
+        default_value = "false",
+        action = ArgAction::SetTrue,
+    )]
+    pub download_avatars: bool,
 }
 
 #[derive(Clone, Debug, Args)]
@@ -118,8 +229,8 @@ pub struct SignatureArgs {
     )]
     pub profile_data: String,
 
-    #[arg(long = "tid", help = "check signature has link to thread or not")]
-    pub tid: String,
+    #[command(flatten)]
+    pub signature_target: SignatureTargetGroups,
 
     #[arg(
         long = "thread-data",
@@ -139,7 +250,7 @@ pub struct SignatureArgs {
 pub struct PointsArgs {
     #[arg(
         long = "changes",
-        help = "path to the file describing changes. Expected to be bbcode format converted from statistics xlsx sheet"
+        help = "path to the file describing changes. Expected to be the statistics xlsx (or xls) sheet, or csv format converted from it"
     )]
     pub changes: String,
 
@@ -155,8 +266,67 @@ pub struct PointsArgs {
     )]
     pub current: String,
 
-    #[arg(short = 'o', long = "output", help = "file to save populated data")]
-    pub output: String,
+    #[arg(
+        long = "workgroup-list",
+        help = "Optional path to the file listing usernames in workgroup, one per line. New users in the list join the workgroup table"
+    )]
+    pub workgroup_list: Option<String>,
+
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "file to save populated data",
+        required_unless_present = "dry_run"
+    )]
+    pub output: Option<String>,
+
+    #[arg(
+        long = "dry-run",
+        help = "print populated data to stdout instead of saving to output file",
+        default_value = "false",
+        action = ArgAction::SetTrue,
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long = "diff",
+        help = "print total points before and after update for users whose points changed",
+        default_value = "false",
+        action = ArgAction::SetTrue,
+    )]
+    pub diff: bool,
+
+    #[arg(
+        long = "ranking",
+        help = "print top N users in both workgroup and general users, ranked by total points"
+    )]
+    pub ranking: Option<usize>,
+
+    #[arg(
+        long = "audit-path",
+        help = "file to save json format points changes on each user, including points before and after changes"
+    )]
+    pub audit_path: Option<String>,
+
+    #[arg(
+        long = "export-csv",
+        help = "file to save csv format points of both workgroup and general users, also saved in dry run"
+    )]
+    pub export_csv: Option<String>,
+
+    #[arg(
+        long = "a-line-pct",
+        help = "ratio of general users above line A, e.g. 0.003 means top 0.3%",
+        default_value = "0.003"
+    )]
+    pub a_line_pct: f64,
+
+    #[arg(
+        long = "b-line-pct",
+        help = "ratio of general users above line B, e.g. 0.01 means top 1%",
+        default_value = "0.01"
+    )]
+    pub b_line_pct: f64,
 }
 
 #[derive(Clone, Debug, Args)]
@@ -169,12 +339,49 @@ pub struct ValidateArgs {
     pub config: String,
 }
 
+#[derive(Clone, Debug, ValueEnum)]
+pub enum ConfigType {
+    /// Toml config used in analyze and validate subcommand.
+    Analyze,
+
+    /// Json extra changes used in points subcommand.
+    Points,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct ConfigArgs {
+    #[arg(long = "type", help = "type of config to generate")]
+    pub config_type: ConfigType,
+
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "file to save the config template, print to stdout if not set"
+    )]
+    pub output: Option<String>,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct CompletionArgs {
+    #[arg(long = "shell", help = "shell to generate completion script for")]
+    pub shell: Shell,
+}
+
 ///////// Subcommand /////////
 
 #[derive(Clone, Debug, Parser)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+
+    #[arg(
+        long = "no-color",
+        help = "disable colored output, also disabled on stdout or stderr that is not a terminal",
+        global = true,
+        default_value = "false",
+        action = ArgAction::SetTrue,
+    )]
+    pub no_color: bool,
 }
 
 #[derive(Clone, Debug, Subcommand)]
@@ -199,6 +406,15 @@ pub enum Command {
 
     #[command(about = "validate poll format in threads")]
     Validate(ValidateArgs),
+
+    #[command(about = "generate template config file with comments on each field")]
+    Config(ConfigArgs),
+
+    #[command(about = "print version and build info")]
+    Version,
+
+    #[command(about = "generate shell completion script to stdout")]
+    Completion(CompletionArgs),
 }
 
 /// Main entry of all subcommands.
@@ -211,5 +427,8 @@ pub async fn run_command_with_args(cli: Cli) -> Result<()> {
         Command::Signature(signature_args) => run_signature_command(signature_args).await,
         Command::Points(points_args) => run_points_command(points_args).await,
         Command::Validate(validate_args) => run_validate_command(validate_args).await,
+        Command::Config(config_args) => run_config_command(config_args).await,
+        Command::Version => run_version_command(),
+        Command::Completion(completion_args) => run_completion_command(completion_args),
     }
 }
@@ -0,0 +1,14 @@
+use crate::cmd::{Cli, CompletionArgs};
+use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::generate;
+use std::io::stdout;
+
+/// Write completion script for the specified shell to stdout.
+///
+/// e.g. for bash: `tm-cli completion --shell bash > /etc/bash_completion.d/tm-cli`
+pub fn run_completion_command(args: CompletionArgs) -> Result<()> {
+    let mut app = Cli::command();
+    generate(args.shell, &mut app, "tm-cli", &mut stdout());
+    Ok(())
+}
@@ -1,14 +1,16 @@
-use crate::utils::{load_thread_data_from_dir, parallel_future};
+use crate::utils::{
+    load_thread_data_from_dir, merge_thread_pages, parallel_future, validate_continuity,
+};
 use anyhow::Result;
 use anyhow::{anyhow, Context};
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::ops::{Add, AddAssign};
 use tm_api::post::{generate_find_post_link, Post as PostModel};
 use tm_api::thread::Thread as ThreadModel;
 use tm_bbcode_macro::bbx;
 use tm_bbcode_webcolor::WebColor;
+use tm_macros::regex;
 
 pub const DUPLICATE_INFO: &str = "重复楼层";
 
@@ -22,12 +24,6 @@ pub(crate) enum ThreadType {
     PollResult,
 }
 
-/// Regex to match choice not voted.
-static UNSELECTED_RE: OnceLock<Regex> = OnceLock::new();
-
-/// Regex to match choice voted.
-static SELECTED_RE: OnceLock<Regex> = OnceLock::new();
-
 #[derive(Debug)]
 enum Choice {
     Selected(String),
@@ -41,6 +37,35 @@ enum ChoiceState {
     Selected,
 }
 
+/// Reason why a poll is invalid.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub(crate) enum ValidationError {
+    /// Choice not in allowed choices.
+    #[error("incorrect choice \"{0}\"")]
+    UnknownChoice(String),
+
+    /// Choice appeared more than once.
+    #[error("multiple choices on \"{0}\"")]
+    DuplicateChoice(String),
+
+    /// Choice not polled, neither selected nor unselected.
+    #[error("didn't poll choice \"{0}\"")]
+    MissingChoice(String),
+
+    /// Count of selected choices is zero or more than allowed.
+    #[error("selected {got} choices which out of range, allowed 1 to {max}")]
+    InvalidSelectionCount { got: usize, max: usize },
+}
+
+/// Join all validation `errors` into a single line message.
+pub(crate) fn join_validation_errors(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 /// Parse the choice in poll line.
 ///
 /// Use this function to validate poll result.
@@ -48,9 +73,9 @@ enum ChoiceState {
 /// Each line shall be in the format of choices with selected state of unselected state.
 fn parse_choice(line: &str) -> Option<Choice> {
     if let Some(capture) =
-        SELECTED_RE.get_or_init(|| Regex::new(r#"^<strong><font color="White"><font style="background-color:Orange">(?<character>[^@<]+)@(?<bangumi>.+)</font></font></strong>$"#).unwrap()).captures(line) {
+        regex!(r#"^<strong><font color="White"><font style="background-color:Orange">(?<character>[^@<]+)@(?<bangumi>.+)</font></font></strong>$"#).captures(line) {
         Some(Choice::Selected(format!("{}@{}", capture.name("character").unwrap().as_str().to_string(), capture.name("bangumi").unwrap().as_str().to_string())))
-    } else if let Some(capture) = UNSELECTED_RE.get_or_init(|| Regex::new(r#"^(?<character>[^@<]+)@(?<bangumi>.+)$"#).unwrap()).captures(line) {
+    } else if let Some(capture) = regex!(r#"^(?<character>[^@<]+)@(?<bangumi>.+)$"#).captures(line) {
         Some(Choice::Unselected(format!("{}@{}", capture.name("character").unwrap().as_str().to_string(), capture.name("bangumi").unwrap().as_str().to_string())))
     } else {
         None
@@ -72,7 +97,7 @@ pub(crate) enum Stage {
 /// Reward to apply
 ///
 /// Some special kinds of reward not listed here because they are mysterious.
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct Reward {
     /// Points ww.
     pub(crate) ww: i32,
@@ -90,9 +115,52 @@ pub(crate) struct Reward {
     pub(crate) credit: i32,
 }
 
+impl Add for Reward {
+    type Output = Reward;
+
+    fn add(mut self, rhs: Reward) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for Reward {
+    fn add_assign(&mut self, rhs: Reward) {
+        self.ww += rhs.ww;
+        self.tsb += rhs.tsb;
+        self.xc = match (self.xc, rhs.xc) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, None) => a,
+            (None, b) => b,
+        };
+        self.energy += rhs.energy;
+        self.credit += rhs.credit;
+    }
+}
+
 impl Reward {
+    /// All kinds of points in reward are zero, or not set.
+    pub(crate) fn is_zero(&self) -> bool {
+        self.ww == 0
+            && self.tsb == 0
+            && self.xc.unwrap_or_default() == 0
+            && self.energy == 0
+            && self.credit == 0
+    }
+
+    /// Sum of ww, tsb, energy and credit.
+    ///
+    /// Points xc is not included.
+    pub(crate) fn total_points(&self) -> i32 {
+        self.ww + self.tsb + self.energy + self.credit
+    }
+
     /// Generate the reward text.
-    fn generate_reward_text(&self) -> String {
+    pub(crate) fn generate_reward_text(&self) -> String {
+        if self.is_zero() {
+            return String::new();
+        }
+
         let ww = if self.ww > 0 {
             Some(format!("{}ww", self.ww))
         } else {
@@ -177,14 +245,121 @@ pub(crate) struct RewardPolicy {
 }
 
 impl RewardPolicy {
-    /// Generate reward description according to the count of missing rounds.
-    pub(crate) fn generate_reward_text(&self, missing_rounds: usize) -> String {
-        match missing_rounds {
-            0 => self.complete.generate_reward_text(),
-            1 => self.missing1.generate_reward_text(),
-            2 => self.missing2.generate_reward_text(),
-            3 => self.missing3.generate_reward_text(),
-            4.. => self.missing4.generate_reward_text(),
+    /// Generate reward description for users in bucket `bucket`.
+    ///
+    /// See [AnalyzeBuckets] for how users are grouped into buckets.
+    pub(crate) fn generate_reward_text(&self, bucket: usize) -> String {
+        self.reward(bucket).generate_reward_text()
+    }
+
+    /// The reward applied on users in bucket `bucket`.
+    ///
+    /// With the default [AnalyzeBuckets], `bucket` is the count of missing rounds.
+    pub(crate) fn reward(&self, bucket: usize) -> &Reward {
+        match bucket {
+            0 => &self.complete,
+            1 => &self.missing1,
+            2 => &self.missing2,
+            3 => &self.missing3,
+            4.. => &self.missing4,
+        }
+    }
+}
+
+/// Boundaries of missing rounds count to group users in analyze result.
+///
+/// Each threshold is the min count of missing rounds in a bucket: bucket `i` holds users missing
+/// `thresholds[i]..thresholds[i + 1]` rounds and the last bucket has no upper bound.
+///
+/// Buckets map to the rewards in [RewardPolicy] in order, the first bucket applies
+/// [RewardPolicy::complete] and the second one applies [RewardPolicy::missing1], etc.
+///
+/// ```toml
+/// [buckets]
+/// # Complete, missing 1 round, missing 2 or more rounds.
+/// thresholds = [0, 1, 2]
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct AnalyzeBuckets {
+    pub thresholds: Vec<usize>,
+}
+
+impl Default for AnalyzeBuckets {
+    /// Complete, missing 1, 2, 3, and 4 or more rounds.
+    fn default() -> Self {
+        Self {
+            thresholds: vec![0, 1, 2, 3, 4],
+        }
+    }
+}
+
+impl AnalyzeBuckets {
+    /// Max count of buckets, same as the count of rewards in [RewardPolicy].
+    pub(crate) const MAX_BUCKETS: usize = 5;
+
+    /// Check thresholds are ascending, starting from 0 and no more than [Self::MAX_BUCKETS].
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.thresholds.first() != Some(&0) {
+            return Err(anyhow!(
+                "invalid bucket thresholds {:?}: shall start from 0",
+                self.thresholds
+            ));
+        }
+        if self.thresholds.len() > Self::MAX_BUCKETS {
+            return Err(anyhow!(
+                "invalid bucket thresholds {:?}: at most {} buckets",
+                self.thresholds,
+                Self::MAX_BUCKETS
+            ));
+        }
+        if self.thresholds.windows(2).any(|x| x[0] >= x[1]) {
+            return Err(anyhow!(
+                "invalid bucket thresholds {:?}: shall be strictly ascending",
+                self.thresholds
+            ));
+        }
+        Ok(())
+    }
+
+    /// Count of buckets.
+    pub(crate) fn len(&self) -> usize {
+        self.thresholds.len()
+    }
+
+    /// Index of the bucket holding users missing `missing_rounds` rounds.
+    pub(crate) fn bucket(&self, missing_rounds: usize) -> usize {
+        self.thresholds
+            .iter()
+            .rposition(|x| *x <= missing_rounds)
+            .unwrap_or_default()
+    }
+
+    /// Key of bucket `idx` in json format analyze result.
+    ///
+    /// "complete" for users missing no round, "missing{n}" for bucket of a single count or the
+    /// last bucket, "missing{start}to{end}" for bucket of a range of counts.
+    pub(crate) fn key(&self, idx: usize) -> String {
+        let start = self.thresholds[idx];
+        match self.thresholds.get(idx + 1) {
+            Some(1) if start == 0 => "complete".to_string(),
+            Some(end) if *end != start + 1 => format!("missing{start}to{}", end - 1),
+            _ => format!("missing{start}"),
+        }
+    }
+
+    /// Human-readable description of bucket `idx`.
+    ///
+    /// e.g. "complete all rounds", "missing 1 round", "missing 2 to 3 rounds".
+    pub(crate) fn describe(&self, idx: usize) -> String {
+        let start = self.thresholds[idx];
+        match self.thresholds.get(idx + 1) {
+            Some(1) if start == 0 => "complete all rounds".to_string(),
+            Some(end) if *end == start + 1 => format!(
+                "missing {start} {}",
+                if start == 1 { "round" } else { "rounds" }
+            ),
+            Some(end) => format!("missing {start} to {} rounds", end - 1),
+            None => format!("missing {start} or more rounds"),
         }
     }
 }
@@ -206,6 +381,10 @@ pub(crate) struct Config {
 
     /// Path to the file containing registration data.
     pub(crate) registration_path: String,
+
+    /// How to group users by missing rounds count in analyze result.
+    #[serde(default)]
+    pub(crate) buckets: AnalyzeBuckets,
 }
 
 impl Config {
@@ -257,18 +436,19 @@ impl Config {
                             if thread.is_empty() {
                                 return Err(anyhow!("empty thread data parsed from file {}", path));
                             }
-                            let mut all_thread_in_the_group = thread
-                                .into_iter()
-                                .map(|x| LoadedThreadPage {
-                                    round: round.clone(),
-                                    group: thread_group.name.clone(),
-                                    name: name.clone(),
-                                    page: x.page,
-                                    tid: x.tid,
-                                    thread: x.thread,
-                                })
-                                .collect::<Vec<_>>();
-                            result.append(&mut all_thread_in_the_group);
+                            validate_continuity(&thread)
+                                .with_context(|| format!("invalid thread data in {path}"))?;
+                            // Merge all pages so that posts are searched in floor order.
+                            let thread = merge_thread_pages(thread)
+                                .with_context(|| format!("when merging thread data from {path}"))?;
+                            result.push(LoadedThreadPage {
+                                round: round.clone(),
+                                group: thread_group.name.clone(),
+                                name: name.clone(),
+                                page: thread.page,
+                                tid: thread.tid,
+                                thread: thread.thread,
+                            });
                         }
 
                         Ok(result)
@@ -487,19 +667,28 @@ pub(crate) struct Thread {
     ///
     /// Actually this field differs among users and not presented in config. But we need a struct
     /// to carry user participation status so keep it here.
-    #[serde(default, skip_serializing)]
+    #[serde(default)]
     pub state: Participation,
 
     /// Floor number of the user participation.
-    #[serde(default, skip_serializing)]
+    #[serde(default)]
     pub floor: usize,
 
     /// Post id.
     ///
     /// Record here to make a redirect link.
-    #[serde(default, skip_serializing)]
+    #[serde(default)]
     pub pid: String,
 
+    /// Reason why the participation is invalid, only set when [state] is
+    /// [Participation::Invalid].
+    ///
+    /// Also carries user participation status, not presented in config.
+    ///
+    /// [state]: Thread::state
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid_reason: Option<String>,
+
     /// Floors violate duplicate poll rule.
     #[serde(default, skip_serializing)]
     pub duplicate: Option<Vec<usize>>,
@@ -581,63 +770,42 @@ impl Thread {
     /// Validate the poll is in correct format or not.
     ///
     /// `poll_data` shall be the html post body data in poll floor.
-    pub fn validate_poll_format(&self, poll_data: &str, post_floor: usize) -> bool {
+    ///
+    /// # Errors
+    ///
+    /// All errors found in the poll, if any.
+    pub fn validate_poll_format(&self, poll_data: &str) -> Result<(), Vec<ValidationError>> {
         let choices = match &self.choices {
             Some(v) => v,
-            None => return true,
+            None => return Ok(()),
         };
         let mut flag_map = choices
             .iter()
             .map(|x| (x, ChoiceState::NotDetermined))
             .collect::<HashMap<&Vec<String>, ChoiceState>>();
 
+        let mut errors = vec![];
+
         // TODO: Url decoding.
         for poll_line in poll_data.replace("&amp;", "&").split("<br />") {
-            match parse_choice(poll_line.trim()) {
-                Some(Choice::Unselected(ch)) => {
-                    match flag_map
-                        .iter_mut()
-                        .find(|(choices, _)| choices.contains(&ch))
-                    {
-                        None => {
-                            println!("invalid poll: thread {} floor {} has incorrect unselected choice \"{}\"", self.name, post_floor, ch);
-                            return false;
-                        }
-                        Some((_, state)) => {
-                            if *state == ChoiceState::Selected || *state == ChoiceState::Unselected
-                            {
-                                println!("invalid poll: thread {} floor {} has multiple unselected choices on \"{}\"", self.name, post_floor, ch);
-                                return false;
-                            }
-
-                            *state = ChoiceState::Unselected;
-                        }
+            let (ch, new_state) = match parse_choice(poll_line.trim()) {
+                Some(Choice::Unselected(ch)) => (ch, ChoiceState::Unselected),
+                Some(Choice::Selected(ch)) => (ch, ChoiceState::Selected),
+                None => continue,
+            };
+            match flag_map
+                .iter_mut()
+                .find(|(choices, _)| choices.contains(&ch))
+            {
+                None => errors.push(ValidationError::UnknownChoice(ch)),
+                Some((_, state)) => {
+                    if *state == ChoiceState::Selected || *state == ChoiceState::Unselected {
+                        errors.push(ValidationError::DuplicateChoice(ch));
+                        continue;
                     }
-                }
-                Some(Choice::Selected(ch)) => {
-                    match flag_map
-                        .iter_mut()
-                        .find(|(choices, _)| choices.contains(&ch))
-                    {
-                        None => {
-                            println!(
-                                "invalid poll: thread {} floor {} has incorrect selected choice {}",
-                                self.name, post_floor, ch
-                            );
-                            return false;
-                        }
-                        Some((_, state)) => {
-                            if *state == ChoiceState::Selected || *state == ChoiceState::Unselected
-                            {
-                                println!("invalid poll: thread {} floor {} has multiple selected choices on {}", self.name, post_floor, ch);
-                                return false;
-                            }
 
-                            *state = ChoiceState::Selected;
-                        }
-                    }
+                    *state = new_state;
                 }
-                None => continue,
             }
         }
 
@@ -646,26 +814,26 @@ impl Thread {
         for (choice, choice_state) in flag_map {
             match choice_state {
                 ChoiceState::NotDetermined => {
-                    println!(
-                        "invalid poll: thread {} floor {} didn't poll choice {:?}",
-                        self.name, post_floor, choice
-                    );
-                    return false;
+                    errors.push(ValidationError::MissingChoice(choice.join("/")))
                 }
                 ChoiceState::Selected => selected_count += 1,
                 ChoiceState::Unselected => continue,
             }
         }
 
-        if selected_count <= 0 || selected_count > self.max_choice.unwrap() {
-            println!(
-                "invalid poll: thread {} floor {} selected {} choices which out of range",
-                self.name, post_floor, selected_count
-            );
-            return false;
+        let max_choice = self.max_choice.unwrap();
+        if selected_count == 0 || selected_count > max_choice {
+            errors.push(ValidationError::InvalidSelectionCount {
+                got: selected_count,
+                max: max_choice,
+            });
         }
 
-        true
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 }
 
@@ -715,9 +883,9 @@ impl LoadedThreadPage {
         }
 
         self.thread
-            .post_list
-            .iter()
-            .find(|x| x.author_id == uid && x.floor != 1 && x.body != BLOCKED_FLOOR_CONTENT)
+            .posts_by_author(uid)
+            .into_iter()
+            .find(|x| x.floor != 1 && x.body != BLOCKED_FLOOR_CONTENT)
     }
 }
 
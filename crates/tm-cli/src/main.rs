@@ -1,4 +1,5 @@
 use crate::cmd::{run_command_with_args, Cli};
+use crate::utils::{init_color, stderr_red};
 use clap::Parser;
 use std::process::exit;
 use tracing::trace;
@@ -7,14 +8,17 @@ use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 mod analyze;
 mod cmd;
+mod completion;
 mod config;
 mod parse;
 mod points;
 mod profile;
 mod signature;
+mod template;
 mod thread;
 mod utils;
 mod validate;
+mod version;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
@@ -31,9 +35,10 @@ async fn main() {
     trace!("starting cli");
 
     let cli = Cli::parse();
+    init_color(cli.no_color);
 
     if let Err(e) = run_command_with_args(cli).await {
-        eprintln!("failed to run command: {e:?}");
+        eprintln!("{}", stderr_red(format!("failed to run command: {e:?}")));
         exit(1)
     }
 }
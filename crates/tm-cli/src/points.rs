@@ -1,17 +1,17 @@
 use crate::cmd::PointsArgs;
+use crate::utils::{green, red};
 use anyhow::{anyhow, bail, Context, Result};
-use regex::Regex;
+use calamine::{open_workbook_auto, Reader};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::OpenOptions;
 use std::io::Write;
-use std::sync::OnceLock;
+use std::path::Path;
 use tm_bbcode_macro::bbx;
 use tm_bbcode_webcolor::WebColor;
+use tm_macros::regex;
 use tokio::fs;
 
-static POINTS_RECORD_RE: OnceLock<Regex> = OnceLock::new();
-
 /// bbx!(
 ///     tr {
 ///         td { {72}, "ID", },
@@ -32,7 +32,7 @@ type ChangesMap = HashMap<String, IncrementRecord>;
 /// Record of a user points status.
 ///
 /// Each record shall be parsed from raw bbcode text in the points statistics thread.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct PointsRecord {
     /// Username.
     ///
@@ -70,10 +70,6 @@ struct PointsRecord {
 }
 
 impl PointsRecord {
-    fn init_re() -> Regex {
-        Regex::new(r#"\[tr]\[td](?<username>[^\[]+)\[/td]\[td](?<points>\d+)\[/td]\[td](?<threads_count>\d+)\[/td]\[td](?<threads_points>\d+)\[/td]\[td](?<special_points>\d+)\[/td]\[td](?<poll_points>\d+)\[/td]\[td](?<energy>\d+)\[/td]\[/tr]"#).unwrap()
-    }
-
     /// Update total points.
     ///
     /// Also, for users reached 100 total points, set the special flag to `true`.
@@ -84,9 +80,12 @@ impl PointsRecord {
         if old_points < 100 && self.points >= 100 {
             self.reach_100_points = true;
             println!(
-                "{} reaches 100 total points (increased {})",
-                self.username,
-                self.points < old_points
+                "{}",
+                green(format!(
+                    "{} reaches 100 total points (increased {})",
+                    self.username,
+                    self.points < old_points
+                ))
             );
         }
     }
@@ -110,8 +109,21 @@ impl PointsRecord {
         self.update_points();
     }
 
+    /// Generate csv record of numeric fields, in the same order as bbcode table columns.
+    fn to_csv_record(&self) -> Vec<String> {
+        vec![
+            self.username.clone(),
+            self.points.to_string(),
+            self.threads_count.to_string(),
+            self.threads_points.to_string(),
+            self.special_points.to_string(),
+            self.poll_points.to_string(),
+            self.energy.to_string(),
+        ]
+    }
+
     fn from_line(line: &str) -> Option<Self> {
-        let cap = match POINTS_RECORD_RE.get_or_init(Self::init_re).captures(line) {
+        let cap = match regex!(r#"\[tr]\[td](?<username>[^\[]+)\[/td]\[td](?<points>\d+)\[/td]\[td](?<threads_count>\d+)\[/td]\[td](?<threads_points>\d+)\[/td]\[td](?<special_points>\d+)\[/td]\[td](?<poll_points>\d+)\[/td]\[td](?<energy>\d+)\[/td]\[/tr]"#).captures(line) {
             Some(v) => v,
             None => return None,
         };
@@ -225,12 +237,105 @@ impl IncrementRecord {
     }
 }
 
+/// Table in points statistics a user belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+enum PointsTable {
+    /// Users in workgroup.
+    Workgroup,
+
+    /// Users not in workgroup.
+    General,
+}
+
+impl PointsTable {
+    fn name(&self) -> &'static str {
+        match self {
+            PointsTable::Workgroup => "Workgroup",
+            PointsTable::General => "General",
+        }
+    }
+}
+
+/// Copy of numeric fields in a [`PointsRecord`].
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+struct PointsSnapshot {
+    /// Total points count.
+    points: i32,
+
+    /// Threads posted.
+    threads_count: i32,
+
+    /// Points on threads posting.
+    threads_points: i32,
+
+    /// Points, the special one.
+    special_points: i32,
+
+    /// Points on polling.
+    poll_points: i32,
+
+    /// Energy value.
+    energy: i32,
+}
+
+impl From<&PointsRecord> for PointsSnapshot {
+    fn from(value: &PointsRecord) -> Self {
+        Self {
+            points: value.points,
+            threads_count: value.threads_count,
+            threads_points: value.threads_points,
+            special_points: value.special_points,
+            poll_points: value.poll_points,
+            energy: value.energy,
+        }
+    }
+}
+
+/// Report of points change applied on a user.
+#[derive(Clone, Debug, Serialize)]
+struct ChangeReport {
+    /// Name of the user.
+    username: String,
+
+    /// Table the user is in after applying change.
+    table: PointsTable,
+
+    /// Points before applying change.
+    ///
+    /// All zero if the user is new.
+    before: PointsSnapshot,
+
+    /// Points after applying change.
+    after: PointsSnapshot,
+
+    /// The change applied.
+    applied: IncrementRecord,
+
+    /// User not in any table before applying change.
+    new_user: bool,
+}
+
+/// Audit record of points change applied on a user.
+#[derive(Debug, Serialize)]
+struct AuditRecord<'a> {
+    /// Points record before applying change.
+    ///
+    /// `None` if the user is new.
+    before: Option<&'a PointsRecord>,
+
+    /// Points record after applying change.
+    after: &'a PointsRecord,
+
+    /// The change applied.
+    applied: &'a IncrementRecord,
+}
+
 /// Record of extra points change on some users.
 ///
 /// The record usually generated from workgroup rewards which may contain poll points and special
 /// points.
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct ExtraRecord {
+pub(crate) struct ExtraRecord {
     /// Name of points kind.
     name: String,
 
@@ -278,7 +383,31 @@ pub async fn run_points_command(args: PointsArgs) -> Result<()> {
     // Load current status.
     let (mut workgroup_data, mut general_data) = load_current_statistics(args.current).await?;
 
-    apply_changes(&mut workgroup_data, &mut general_data, &user_changes);
+    let workgroup_users = if let Some(workgroup_list_path) = args.workgroup_list {
+        load_workgroup_list(workgroup_list_path)
+            .await
+            .context("failed to load workgroup list")?
+    } else {
+        HashSet::new()
+    };
+
+    // Records before applying changes, only used when saving audit trail.
+    let before_data = if args.audit_path.is_some() {
+        workgroup_data
+            .iter()
+            .chain(general_data.iter())
+            .cloned()
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+
+    let reports = apply_changes(
+        &mut workgroup_data,
+        &mut general_data,
+        &user_changes,
+        &workgroup_users,
+    );
 
     println!("Workgroup users count: {}", workgroup_data.len());
     println!("General users count: {}", general_data.len());
@@ -286,27 +415,73 @@ pub async fn run_points_command(args: PointsArgs) -> Result<()> {
     // println!("Workgroup users points after update: {workgroup_data:#?}");
     // println!("General users points after update: {general_data:#?}");
 
+    if args.diff {
+        print_points_diff(&reports);
+    }
+
     workgroup_data.sort_by(|a, b| b.points.cmp(&a.points));
     general_data.sort_by(|a, b| b.points.cmp(&a.points));
 
-    let bbcode_result = generate_bbcode_result(&workgroup_data, &general_data);
+    if let Some(audit_path) = args.audit_path {
+        println!("writing audit records to {audit_path}");
+        let audit_records = generate_audit_records(
+            &before_data,
+            workgroup_data.iter().chain(general_data.iter()),
+            &user_changes,
+        );
+        let data = serde_json::to_string_pretty(&audit_records)
+            .context("failed to serialize audit records")?;
+        fs::write(audit_path.as_str(), data)
+            .await
+            .with_context(|| format!("failed to save audit records to {audit_path}"))?;
+    }
+
+    if let Some(csv_path) = args.export_csv {
+        println!("writing csv data to {csv_path}");
+        export_csv(csv_path.as_str(), &workgroup_data, &general_data)?;
+        println!("csv data saved in {csv_path}");
+    }
+
+    if let Some(n) = args.ranking {
+        print_ranking(&workgroup_data, &general_data, n);
+    }
+
+    let bbcode_result = generate_bbcode_result(
+        &workgroup_data,
+        &general_data,
+        args.a_line_pct,
+        args.b_line_pct,
+    );
 
     println!("users reached 100 total points:");
     for user_record in workgroup_data.iter().filter(|x| x.reach_100_points) {
         // TODO: Use BBCode macro.
-        println!("{}", user_record.username);
+        println!("{}", green(&user_record.username));
     }
     for user_record in general_data.iter().filter(|x| x.reach_100_points) {
         // TODO: Use BBCode macro.
-        println!("{}", user_record.username);
+        println!("{}", green(&user_record.username));
     }
     println!();
 
+    if args.dry_run {
+        println!("{bbcode_result}");
+        println!();
+        println!(
+            "dry run: {} records changed ({} new users), output file not written",
+            reports.len(),
+            reports.iter().filter(|x| x.new_user).count()
+        );
+        return Ok(());
+    }
+
+    // Output is required unless in dry run.
+    let output = args.output.unwrap();
     let mut file = OpenOptions::new()
         .write(true)
         .create(true)
         .truncate(true)
-        .open(args.output)
+        .open(output)
         .context("failed to open output file")?;
     file.write(bbcode_result.as_bytes())?;
     println!("done");
@@ -316,31 +491,32 @@ pub async fn run_points_command(args: PointsArgs) -> Result<()> {
 
 /// Populate changes from increment data.
 ///
-/// The increment data in converted from statistics xlsx file.
+/// The increment data in converted from statistics xlsx file, or the xlsx file itself.
 /// Records in the that data are only expected to have poll points and energy, none special points.
 async fn populate_increment_record(data_path: String) -> Result<Vec<IncrementRecord>> {
-    let mut csv = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .double_quote(true)
-        .from_path(data_path)?;
+    let rows = if is_spreadsheet(&data_path) {
+        load_spreadsheet_rows(&data_path)?
+    } else {
+        load_csv_rows(&data_path)?
+    };
 
-    let energy_re = Regex::new(r#"(?<energy>\d+)能量值"#).expect("invalid points kind regex");
-    let poll_points_re = Regex::new(r#"(?<points>\d+)积分"#).expect("invalid points kind regex");
+    let energy_re = regex!(r#"(?<energy>\d+)能量值"#);
+    let poll_points_re = regex!(r#"(?<points>\d+)积分"#);
 
     let mut records = vec![];
 
-    // Skip the title row.
-    for maybe_record in csv.records().into_iter() {
-        let record = match maybe_record {
-            Ok(v) => v,
-            Err(e) => return Err(anyhow!("invalid increment record: {}", e)),
-        };
-
+    for row in rows {
         //   0, 1,  2,      3,  4,      5,
         // 楼层,ID,UID,参与范围,积分,宣传签名,备注,备注2（说明）,备注3（工具）
-        let floor = record.get(0).unwrap();
-        let username = record.get(1).unwrap().to_string();
-        let points = record.get(4).unwrap();
+        let (Some(_floor), Some(username), Some(points)) = (row.first(), row.get(1), row.get(4))
+        else {
+            bail!(
+                "invalid increment record: expected at least 5 columns, got {}",
+                row.len()
+            );
+        };
+        let username = username.to_string();
+        let points = points.as_str();
 
         let energy_capture = energy_re.captures(points);
         let poll_points_capture = poll_points_re.captures(points);
@@ -381,6 +557,44 @@ async fn populate_increment_record(data_path: String) -> Result<Vec<IncrementRec
     Ok(records)
 }
 
+/// Check `data_path` is a spreadsheet file or not, by the file extension.
+fn is_spreadsheet(data_path: &str) -> bool {
+    Path::new(data_path)
+        .extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|x| x.eq_ignore_ascii_case("xlsx") || x.eq_ignore_ascii_case("xls"))
+}
+
+/// Load all rows in csv file `data_path`.
+fn load_csv_rows(data_path: &str) -> Result<Vec<Vec<String>>> {
+    let mut csv = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .double_quote(true)
+        .from_path(data_path)?;
+
+    csv.records()
+        .map(|maybe_record| match maybe_record {
+            Ok(v) => Ok(v.iter().map(|x| x.to_string()).collect()),
+            Err(e) => Err(anyhow!("invalid increment record: {}", e)),
+        })
+        .collect()
+}
+
+/// Load all rows in the first sheet of xlsx or xls file `data_path`.
+fn load_spreadsheet_rows(data_path: &str) -> Result<Vec<Vec<String>>> {
+    let mut workbook = open_workbook_auto(data_path)
+        .with_context(|| format!("failed to open spreadsheet {data_path}"))?;
+    let sheet = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| anyhow!("no sheet in spreadsheet {data_path}"))?
+        .with_context(|| format!("failed to read the first sheet in {data_path}"))?;
+
+    Ok(sheet
+        .rows()
+        .map(|row| row.iter().map(|x| x.to_string()).collect())
+        .collect())
+}
+
 /// Populate points changes from extra json data.
 ///
 /// The data usually came from workgroup rewards.
@@ -441,6 +655,21 @@ async fn load_current_statistics(path: String) -> Result<(Vec<PointsRecord>, Vec
     Ok((workgroup_data, general_data))
 }
 
+/// Load usernames of workgroup members from file, one username per line.
+///
+/// Empty lines are ignored.
+async fn load_workgroup_list(path: String) -> Result<HashSet<String>> {
+    let content = fs::read_to_string(path)
+        .await
+        .context("failed to read workgroup list")?;
+    Ok(content
+        .lines()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string())
+        .collect())
+}
+
 /// Apply changes in `changes_map` on `workgroup_data` and `general_data`.
 ///
 /// ## Parameters
@@ -448,44 +677,103 @@ async fn load_current_statistics(path: String) -> Result<(Vec<PointsRecord>, Vec
 /// * `workgroup_data`: Current points state for moe workgroup users.
 /// * `general_data`: Current points state for general users those not in moe workgroup.
 /// * `changes_map`: Hash map, key is username, value is all the changes need to apply on user.
+/// * `workgroup_users`: Usernames of moe workgroup users, to decide which table new users join.
 ///
 /// ## Steps
 ///
 /// This function iterate through the `changes_map`, for each user in map:
 ///
 /// 1. If the user is in `workgroup_data`, add the changes on the same user record in
-///   `workgroup_data`.
+///    `workgroup_data`.
 /// 2. If the user is in `general_data`, add the changes on the same user record in
-///   `general_data`.
+///    `general_data`.
 /// 3. If the user presents in neither `workgroup_data` nor `general_data`, then the user gained
-///   moe rewards for the first time and append the data to the end of `general_data`. Perhaps we
-///   can use extra configs to specify each new user is in workgroup or not, this step makes further
-///   table sorting possible, but not implemented yet.
+///    moe rewards for the first time and append the data to the end of `workgroup_data` if the user
+///    is in `workgroup_users`, otherwise to the end of `general_data`.
+///
+/// ## Returns
+///
+/// Reports of changes applied on each user, ordered by table and username.
 fn apply_changes(
     workgroup_data: &mut Vec<PointsRecord>,
     general_data: &mut Vec<PointsRecord>,
     changes_map: &ChangesMap,
-) {
+    workgroup_users: &HashSet<String>,
+) -> Vec<ChangeReport> {
+    let mut reports = vec![];
     for (username, change) in changes_map.iter() {
-        if let Some(workgroup_record) = workgroup_data
+        let (table, before, new_user, after) = if let Some(workgroup_record) = workgroup_data
             .iter_mut()
             .find(|x| x.username.as_str() == username)
         {
+            let before = PointsSnapshot::from(&*workgroup_record);
             workgroup_record.apply_change(change);
+            (PointsTable::Workgroup, before, false, workgroup_record)
         } else if let Some(general_record) = general_data
             .iter_mut()
             .find(|x| x.username.as_str() == username)
         {
+            let before = PointsSnapshot::from(&*general_record);
             general_record.apply_change(change);
+            (PointsTable::General, before, false, general_record)
+        } else if workgroup_users.contains(username) {
+            workgroup_data.push(change.into());
+            (
+                PointsTable::Workgroup,
+                PointsSnapshot::default(),
+                true,
+                workgroup_data.last_mut().unwrap(),
+            )
         } else {
-            general_data.push(change.into())
-        }
+            general_data.push(change.into());
+            (
+                PointsTable::General,
+                PointsSnapshot::default(),
+                true,
+                general_data.last_mut().unwrap(),
+            )
+        };
+        reports.push(ChangeReport {
+            username: username.clone(),
+            table,
+            before,
+            after: PointsSnapshot::from(&*after),
+            applied: change.clone(),
+            new_user,
+        });
     }
+    reports.sort_by(|a, b| (a.table, &a.username).cmp(&(b.table, &b.username)));
+    reports
+}
+
+/// Generate audit records for users in `after_data` having changes in `changes_map`.
+fn generate_audit_records<'a>(
+    before_data: &'a [PointsRecord],
+    after_data: impl Iterator<Item = &'a PointsRecord>,
+    changes_map: &'a ChangesMap,
+) -> Vec<AuditRecord<'a>> {
+    let before_records = before_data
+        .iter()
+        .map(|x| (x.username.as_str(), x))
+        .collect::<HashMap<_, _>>();
+    after_data
+        .filter_map(|after| {
+            changes_map
+                .get(after.username.as_str())
+                .map(|applied| AuditRecord {
+                    before: before_records.get(after.username.as_str()).copied(),
+                    after,
+                    applied,
+                })
+        })
+        .collect()
 }
 
 fn generate_bbcode_result(
     workgroup_data: &Vec<PointsRecord>,
     general_data: &Vec<PointsRecord>,
+    a_pct: f64,
+    b_pct: f64,
 ) -> String {
     let workgroup_lines = workgroup_data
         .iter()
@@ -498,10 +786,8 @@ fn generate_bbcode_result(
         .collect::<Vec<_>>()
         .join("\n");
 
-    // Line A is 0.3%
-    let line_a = general_data[(general_data.len() as f64 * 0.003).ceil() as usize - 1].points;
-    // Line B is 1%
-    let line_b = general_data[(general_data.len() as f64 * 0.01).ceil() as usize - 1].points;
+    let line_a = line_points(general_data, a_pct);
+    let line_b = line_points(general_data, b_pct);
 
     let header = generate_header();
 
@@ -544,6 +830,113 @@ fn generate_bbcode_result(
     )
 }
 
+/// Save records in `workgroup_data` and `general_data` to csv file `csv_path`.
+///
+/// The leading "Table" column tells which table the record is in.
+fn export_csv(
+    csv_path: &str,
+    workgroup_data: &[PointsRecord],
+    general_data: &[PointsRecord],
+) -> Result<()> {
+    let mut builder = csv::WriterBuilder::new()
+        .double_quote(true)
+        .from_path(csv_path)
+        .with_context(|| format!("failed to open csv output file {csv_path}"))?;
+
+    builder
+        .write_record([
+            "Table",
+            "username",
+            "points",
+            "threads_count",
+            "threads_points",
+            "special_points",
+            "poll_points",
+            "energy",
+        ])
+        .with_context(|| format!("failed to write csv header to {csv_path}"))?;
+
+    for (table, records) in [
+        (PointsTable::Workgroup, workgroup_data),
+        (PointsTable::General, general_data),
+    ] {
+        for record in records {
+            let mut csv_record = vec![table.name().to_string()];
+            csv_record.extend(record.to_csv_record());
+            builder
+                .write_record(csv_record.as_slice())
+                .with_context(|| {
+                    format!("failed to write csv record \"{csv_record:?}\" to {csv_path}")
+                })?;
+        }
+    }
+
+    builder
+        .flush()
+        .with_context(|| format!("failed to flush csv output file {csv_path}"))
+}
+
+/// Points of the user at top `pct` in `general_data`, which is sorted by points in descending
+/// order.
+///
+/// Use the max points if the position is before the first user, or the min points if after the
+/// last user. 0 if `general_data` is empty.
+fn line_points(general_data: &[PointsRecord], pct: f64) -> i32 {
+    let pos = (general_data.len() as f64 * pct).ceil() as usize;
+    let idx = pos
+        .saturating_sub(1)
+        .min(general_data.len().saturating_sub(1));
+    general_data.get(idx).map(|x| x.points).unwrap_or_default()
+}
+
+/// Print total points changes in `reports`.
+///
+/// New users have 0 points before. Users with no change on total points are omitted. Delta is
+/// colored if colored output is enabled.
+fn print_points_diff(reports: &[ChangeReport]) {
+    println!("points diff:");
+    println!("{:<24}{:>8}{:>8}{:>8}", "username", "old", "new", "delta");
+    for report in reports {
+        let (old_points, new_points) = (report.before.points, report.after.points);
+        let delta = new_points - old_points;
+        if delta == 0 {
+            continue;
+        }
+        let delta_text = format!("{delta:>+8}");
+        let delta_text = if delta > 0 {
+            green(delta_text)
+        } else {
+            red(delta_text)
+        };
+        println!(
+            "{:<24}{:>8}{:>8}{}",
+            report.username, old_points, new_points, delta_text
+        );
+    }
+    println!();
+}
+
+/// Print top `n` users in `workgroup_data` and `general_data` ranked by total points.
+///
+/// Users with the same points share the same rank, like "1, 2, 2, 4".
+fn print_ranking(workgroup_data: &[PointsRecord], general_data: &[PointsRecord], n: usize) {
+    let mut all_data = workgroup_data
+        .iter()
+        .chain(general_data.iter())
+        .collect::<Vec<_>>();
+    all_data.sort_by_key(|x| std::cmp::Reverse(x.points));
+
+    println!("top {n} users:");
+    let mut rank = 0;
+    for (idx, record) in all_data.iter().take(n).enumerate() {
+        if idx == 0 || all_data[idx - 1].points != record.points {
+            rank = idx + 1;
+        }
+        println!("{rank}. {}: {}pts", record.username, record.points);
+    }
+    println!();
+}
+
 /// Header row in points table.
 fn generate_header() -> String {
     bbx!(
@@ -1,11 +1,12 @@
 use crate::cmd::ProfileArgs;
 use crate::utils::{ask_delete_if_exists, load_thread_data_from_dir};
 use anyhow::{Context, Result};
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 use std::time::Duration;
-use tm_api::profile::{fetch_user_profile_by_id, fetch_user_profile_by_name};
+use tm_api::profile::{fetch_user_profile_by_id, fetch_user_profile_by_name, Profile};
+use tm_api::{ApiClient, RateLimiter};
 use tokio::fs;
-use tokio::time::sleep;
 
 pub async fn run_profile_command(args: ProfileArgs) -> Result<()> {
     if let Some(name) = args.profile_target.name {
@@ -36,13 +37,24 @@ pub async fn run_profile_command(args: ProfileArgs) -> Result<()> {
         let reg_data = load_thread_data_from_dir(reg_dir.as_str())
             .await
             .with_context(|| format!("when loading registration thread data from dir {reg_dir}"))?;
+        let client =
+            ApiClient::new().with_rate_limiter(RateLimiter::new(Duration::from_millis(700)));
+        // Avatars are on image servers, use a separate client with shorter delay.
+        let avatar_client = if args.download_avatars {
+            fs::create_dir(output_dir_path.join("avatars")).await?;
+            Some(ApiClient::new().with_rate_limiter(RateLimiter::new(Duration::from_millis(200))))
+        } else {
+            None
+        };
+        let mut profiles = vec![];
         for reg in reg_data {
             println!("downloading data for tid={}, page={}", reg.tid, reg.page);
             for post in reg.thread.post_list {
                 println!("downloading for floor={} pid={}", post.floor, post.id);
                 let uid = post.author_id;
 
-                let profile = fetch_user_profile_by_id(uid.as_str())
+                let profile = client
+                    .fetch_user_profile_by_id(uid.as_str())
                     .await
                     .with_context(|| format!("failed to fetch profile for uid={uid}"))?;
 
@@ -58,10 +70,53 @@ pub async fn run_profile_command(args: ProfileArgs) -> Result<()> {
                     )
                 })?;
 
-                sleep(Duration::from_millis(700)).await;
+                if let (Some(avatar_client), Some(avatar)) =
+                    (avatar_client.as_ref(), profile.avatar.as_ref())
+                {
+                    println!("downloading avatar for uid={uid}");
+                    let data = avatar_client
+                        .fetch_avatar(avatar)
+                        .await
+                        .with_context(|| format!("failed to fetch avatar for uid={uid}"))?;
+                    let mut avatar_path = output_dir_path.join("avatars");
+                    avatar_path.push(format!("{uid}.{}", avatar_extension(avatar)));
+                    fs::write(avatar_path, data)
+                        .await
+                        .with_context(|| format!("failed to save avatar for uid={uid}"))?;
+                }
+                profiles.push((post.floor, profile));
+            }
+        }
+
+        if !args.filter_group.is_empty() {
+            profiles.retain(|(_, profile)| {
+                profile
+                    .user_group
+                    .as_ref()
+                    .is_some_and(|x| args.filter_group.contains(x))
+            });
+            println!(
+                "{} users in groups {:?}:",
+                profiles.len(),
+                args.filter_group
+            );
+            for (floor, profile) in profiles.iter() {
+                println!(
+                    "floor={} username={} uid={} user_group={}",
+                    floor,
+                    profile.username,
+                    profile.uid,
+                    profile.user_group.as_deref().unwrap_or_default()
+                );
             }
         }
 
+        if let Some(csv_path) = args.export_csv {
+            println!("writing csv data to {csv_path}");
+            export_csv(csv_path.as_str(), &profiles)?;
+            println!("csv data saved in {csv_path}");
+        }
+
         return Ok(());
     }
 
@@ -74,3 +129,82 @@ pub async fn run_profile_command(args: ProfileArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Guess the image file extension from avatar `url`, fallback to "jpg".
+///
+/// Avatar urls like "uc_server/avatar.php?uid=1" have no extension in path.
+fn avatar_extension(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let file_name = path.rsplit('/').next().unwrap_or_default();
+    match file_name.rsplit_once('.') {
+        Some((_, ext))
+            if matches!(
+                ext.to_ascii_lowercase().as_str(),
+                "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp"
+            ) =>
+        {
+            ext
+        }
+        _ => "jpg",
+    }
+}
+
+/// Write summary of `profiles` on each floor to csv file `csv_path`.
+///
+/// Each credit type found in any profile has its own column, sorted by name.
+fn export_csv(csv_path: &str, profiles: &[(usize, Profile)]) -> Result<()> {
+    let credit_names = profiles
+        .iter()
+        .flat_map(|(_, profile)| profile.credits.keys())
+        .collect::<BTreeSet<_>>();
+
+    let mut builder = csv::WriterBuilder::new()
+        .double_quote(true)
+        .from_path(csv_path)
+        .with_context(|| format!("failed to open csv output file {csv_path}"))?;
+
+    let mut header = [
+        "floor",
+        "username",
+        "uid",
+        "user_group",
+        "moderator_group",
+        "register_time",
+        "last_visit_time",
+        "check_in_days_count",
+    ]
+    .map(String::from)
+    .to_vec();
+    header.extend(credit_names.iter().map(|x| x.to_string()));
+    builder
+        .write_record(header)
+        .with_context(|| format!("failed to write csv header to {csv_path}"))?;
+
+    for (floor, profile) in profiles {
+        let mut record = vec![
+            floor.to_string(),
+            profile.username.clone(),
+            profile.uid.clone(),
+            profile.user_group.clone().unwrap_or_default(),
+            profile.moderator_group.clone().unwrap_or_default(),
+            profile.register_time.clone().unwrap_or_default(),
+            profile.last_visit_time.clone().unwrap_or_default(),
+            profile
+                .check_in_days_count
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+        ];
+        record.extend(
+            credit_names
+                .iter()
+                .map(|x| profile.credits.get(*x).cloned().unwrap_or_default()),
+        );
+        builder
+            .write_record(record.as_slice())
+            .with_context(|| format!("failed to write csv record \"{record:?}\" to {csv_path}"))?;
+    }
+
+    builder
+        .flush()
+        .with_context(|| format!("failed to flush csv output file {csv_path}"))
+}
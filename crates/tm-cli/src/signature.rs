@@ -2,14 +2,15 @@ use crate::cmd::SignatureArgs;
 use crate::utils::load_thread_data_from_dir;
 use anyhow::{Context, Result};
 use regex::Regex;
+use select::document::Document;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::PathBuf;
 use tm_api::profile::Profile;
+use tm_html::HtmlElementExt;
 use tokio::fs;
 
 pub async fn run_signature_command(args: SignatureArgs) -> Result<()> {
-    let tid = args.tid;
     let profiles = load_profile_data_from_dir(args.profile_data.as_str())
         .await
         .with_context(|| format!("when loading profile from {}", args.profile_data))?;
@@ -20,13 +21,26 @@ pub async fn run_signature_command(args: SignatureArgs) -> Result<()> {
         r#"href="https://(www\.)?tsdm39\.(com|net)/forum\.php\?mod=viewthread&amp;tid=tid"#,
     );
 
-    let target = Regex::new(
-        format!(
-            r#"href="https://(www\.)?tsdm39\.(com|net)/forum\.php\?mod=viewthread&amp;tid={tid}"#
-        )
-        .as_str(),
-    )
-    .expect("invalid target url regex");
+    let target = match args.signature_target.pattern {
+        Some(pattern) => Regex::new(pattern.as_str())
+            .with_context(|| format!("invalid link pattern {pattern}"))?,
+        None => {
+            let tids = args
+                .signature_target
+                .tids
+                .iter()
+                .map(|x| regex::escape(x.as_str()))
+                .collect::<Vec<_>>()
+                .join("|");
+            Regex::new(
+                format!(
+                    r#"^https://(www\.)?tsdm39\.(com|net)/forum\.php\?mod=viewthread&tid=({tids})\b"#
+                )
+                .as_str(),
+            )
+            .expect("invalid target url regex")
+        }
+    };
 
     for profile in profiles {
         let signature = match profile.signature {
@@ -34,7 +48,15 @@ pub async fn run_signature_command(args: SignatureArgs) -> Result<()> {
             None => continue,
         };
 
-        if target.is_match(signature.as_str()) {
+        let has_target_link = Document::from(signature.as_str())
+            .nth(0)
+            .map(|x| {
+                x.extract_links()
+                    .iter()
+                    .any(|(href, _)| target.is_match(href))
+            })
+            .unwrap_or(false);
+        if has_target_link {
             users_have_content.push(profile.clone());
             continue;
         }
@@ -75,7 +97,7 @@ pub async fn run_signature_command(args: SignatureArgs) -> Result<()> {
             sorts.sort_by(|(_, f1), (_, f2)| f1.cmp(f2));
 
             sorts.iter().for_each(|(uid, floor)| {
-                if let Some(u) = users_have_content.iter().find(|y| y.uid == *uid) {
+                if let Some(u) = users_have_content.iter().find(|y| uid.as_str() == y.uid) {
                     println!("{}({} #{})", u.username, u.uid, floor);
                     if output_target.is_some() {
                         tmp_floors.push(format!("{floor}"))
@@ -0,0 +1,173 @@
+use crate::cmd::{ConfigArgs, ConfigType};
+use crate::config::Reward;
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use tokio::fs;
+
+pub async fn run_config_command(args: ConfigArgs) -> Result<()> {
+    let template = match args.config_type {
+        ConfigType::Analyze => generate_analyze_template(),
+        ConfigType::Points => generate_points_template(),
+    };
+
+    match args.output {
+        Some(output) => {
+            fs::write(output.as_str(), template)
+                .await
+                .with_context(|| format!("failed to save config template to {output}"))?;
+            println!("config template saved in {output}");
+        }
+        None => print!("{template}"),
+    }
+
+    Ok(())
+}
+
+/// Generate template of [Config] used in analyze and validate subcommand.
+///
+/// Each field is described by the comment above it, follow the structure in [Config].
+///
+/// [Config]: crate::config::Config
+fn generate_analyze_template() -> String {
+    let mut t = String::new();
+
+    // Config.
+    t.push_str("# Stage to analyze, \"Season\" or \"Ending\".\n");
+    t.push_str("stage = \"Season\"\n\n");
+    t.push_str(
+        "# Path to the dir holding registration thread data, downloaded by thread subcommand.\n",
+    );
+    t.push_str("registration_path = \"raw/00_registration\"\n\n");
+
+    // RewardPolicy.
+    t.push_str("# Reward applied on users, according to the count of missed rounds.\n");
+    t.push_str(
+        "# missing1 to missing4 are for users missed 1 to 4 (or more) rounds, all optional.\n",
+    );
+    for (policy, ww, tsb, energy, credit) in [
+        ("complete", 50, 100, 2, 10),
+        ("missing1", 40, 80, 0, 0),
+        ("missing2", 30, 60, 0, 0),
+    ] {
+        let reward = Reward {
+            ww,
+            tsb,
+            xc: None,
+            energy,
+            credit,
+        };
+        generate_reward_template(&mut t, policy, &reward);
+    }
+
+    // AnalyzeBuckets.
+    t.push_str("# Optional, group users by the count of missed rounds, each value is the min count of a group.\n");
+    t.push_str("# Groups apply rewards above in order, default to [0, 1, 2, 3, 4].\n");
+    t.push_str("# [buckets]\n");
+    t.push_str("# thresholds = [0, 1, 2]\n\n");
+
+    // Round, ThreadGroup and Thread.
+    t.push_str(
+        "# Rounds in stage, users missed any thread in a round are considered missing the round.\n",
+    );
+    t.push_str("# A round consists of groups, a group consists of threads.\n");
+    t.push_str("[[round]]\n");
+    t.push_str("# Name of round.\n");
+    t.push_str("name = \"round 1\"\n\n");
+    t.push_str("# Group with a name, threads are listed as \"name【thread1；thread2】\".\n");
+    t.push_str("# Each thread has a name, the path to dir of thread data downloaded by thread subcommand,\n");
+    t.push_str("# and thread_type, \"Poll\" or \"PollResult\".\n");
+    t.push_str("[[round.group]]\n");
+    t.push_str("name = \"preliminary\"\n");
+    t.push_str("thread = [\n");
+    for (name, path) in [("A", "raw/01_preliminary_a"), ("B", "raw/01_preliminary_b")] {
+        generate_thread_template(&mut t, name, path, "Poll");
+    }
+    t.push_str("]\n\n");
+    t.push_str("# Group without name, usually holds a single thread.\n");
+    t.push_str("[[round.group]]\n");
+    t.push_str("thread = [\n");
+    generate_thread_template(&mut t, "result", "raw/01_preliminary_result", "PollResult");
+    t.push_str("]\n\n");
+    t.push_str("# Optional fields in thread:\n");
+    t.push_str("#\n");
+    t.push_str(
+        "# * choices: All allowed choices in poll, each choice is a list of accepted spellings.\n",
+    );
+    t.push_str("#   choices = [[\"character@bangumi\", \"misspelled character@bangumi\"]]\n");
+    t.push_str("# * max_choice: Max count of choices allowed to select in poll.\n");
+    t.push_str("#   max_choice = 2\n");
+    t.push_str("# * revised: Floors revised as valid poll manually.\n");
+    t.push_str("#   revised = [12, 34]\n");
+    t.push_str("# * duplicate: Floors violating duplicate poll rule.\n");
+    t.push_str("#   duplicate = [56]\n");
+
+    t
+}
+
+/// Generate template of `reward` in reward policy `policy`.
+fn generate_reward_template(t: &mut String, policy: &str, reward: &Reward) {
+    let _ = writeln!(t, "[reward_policy.{policy}]");
+    let _ = writeln!(t, "# Points ww.");
+    let _ = writeln!(t, "ww = {}", reward.ww);
+    let _ = writeln!(t, "# Points tsb.");
+    let _ = writeln!(t, "tsb = {}", reward.tsb);
+    let _ = writeln!(t, "# Points xc, optional.");
+    let _ = writeln!(t, "# xc = {}", reward.xc.unwrap_or_default());
+    let _ = writeln!(t, "# Moe energy.");
+    let _ = writeln!(t, "energy = {}", reward.energy);
+    let _ = writeln!(t, "# Moe credit.");
+    let _ = writeln!(t, "credit = {}", reward.credit);
+    t.push('\n');
+}
+
+/// Generate template of a [Thread] in thread group.
+///
+/// [Thread]: crate::config::Thread
+fn generate_thread_template(t: &mut String, name: &str, path: &str, thread_type: &str) {
+    let _ = writeln!(
+        t,
+        "    {{ name = \"{name}\", path = \"{path}\", thread_type = \"{thread_type}\" }},"
+    );
+}
+
+/// Generate template of extra records used in `--extra-changes` in points subcommand.
+///
+/// The points subcommand has no toml config, the extra changes file is a json array and json has no
+/// comments, so all descriptions are in the leading "_comment" field which is ignored when
+/// loading.
+fn generate_points_template() -> String {
+    let mut t = String::new();
+    t.push_str("[\n");
+    t.push_str("  {\n");
+    t.push_str("    \"_comment\": \"name: kind of points, one of 特殊积分, 投票积分, 发帖数量 and 发帖积分. value: points to add. users: usernames to add points on\",\n");
+    t.push_str("    \"name\": \"特殊积分\",\n");
+    t.push_str("    \"value\": 10,\n");
+    t.push_str("    \"users\": [\"username1\", \"username2\"]\n");
+    t.push_str("  },\n");
+    t.push_str("  {\n");
+    t.push_str("    \"name\": \"投票积分\",\n");
+    t.push_str("    \"value\": 5,\n");
+    t.push_str("    \"users\": [\"username3\"]\n");
+    t.push_str("  }\n");
+    t.push_str("]\n");
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::points::ExtraRecord;
+
+    #[test]
+    fn test_analyze_template_parses() {
+        toml::from_str::<Config>(&generate_analyze_template()).unwrap();
+    }
+
+    #[test]
+    fn test_points_template_parses() {
+        let records =
+            serde_json::from_str::<Vec<ExtraRecord>>(&generate_points_template()).unwrap();
+        assert_eq!(records.len(), 2);
+    }
+}
@@ -1,11 +1,12 @@
 use crate::cmd::ThreadArgs;
-use crate::utils::ask_delete_if_exists;
-use anyhow::{Context, Result};
+use crate::utils::{ask_delete_if_exists, progress_bar, yellow};
+use anyhow::{anyhow, Context, Result};
 use std::path::PathBuf;
 use std::time::Duration;
-use tm_api::thread::fetch_thread_content;
+use tm_api::thread::Thread;
+use tm_api::{ApiClient, ApiConfig, RateLimiter};
+use tm_types::{PageNumber, ThreadId};
 use tokio::fs;
-use tokio::time::sleep;
 use tracing::trace;
 
 pub async fn run_thread_command(args: ThreadArgs) -> Result<()> {
@@ -25,29 +26,62 @@ pub async fn run_thread_command(args: ThreadArgs) -> Result<()> {
         .context("when creating output_dir")?;
 
     let tid = args.tid;
+    let client = build_client(
+        args.retry,
+        args.retry_backoff,
+        args.delay,
+        args.concurrent as usize,
+    );
 
-    // Fetch all pages in thread.
+    // Fetch all pages in thread, within range [from_page, to_page].
     if args.all == Some(true) {
-        let mut page = 1;
-        let mut total_pages = 1;
+        let from_page = args.from_page.get();
+        if let Some(to_page) = args.to_page {
+            if to_page.get() < from_page {
+                return Err(anyhow!(
+                    "invalid page range: --to-page {to_page} is less than --from-page {from_page}"
+                ));
+            }
+        }
+
+        let concurrent = args.concurrent as usize;
+        if concurrent > 3 {
+            println!(
+                "{}",
+                yellow(format!("WARNING: fetching {concurrent} pages concurrently, too many requests may get banned by the server"))
+            );
+        }
 
-        while page <= total_pages {
-            println!("fetch page: tid={tid}, page={page}, total_pages={total_pages}");
+        println!("fetch all pages: tid={tid}");
+        let bar = progress_bar(0, "fetching pages");
+        let pages = client
+            .fetch_thread_pages_with_progress(
+                tid,
+                args.from_page,
+                args.to_page,
+                |fetched, total| {
+                    bar.set_length(total as u64);
+                    bar.set_position(fetched as u64);
+                },
+            )
+            .await?;
+        bar.finish_and_clear();
+
+        let total_pages = match pages.first() {
+            Some(v) => v.total_pages(),
+            None => {
+                println!("no page to fetch: --from-page {from_page} exceeds total pages");
+                return Ok(());
+            }
+        };
+        println!(
+            "fetched pages {from_page} to {}, total_pages={total_pages}",
+            from_page + pages.len() as u32 - 1
+        );
+        for (page, content) in (from_page..).zip(pages.iter()) {
             let mut output_file = output_dir_path.clone();
             output_file.push(format!("{tid}_{page}.json"));
-            let (post_per_page, total_post) = download_single_page(output_file, tid, page)
-                .await
-                .context("when downloading single page")?;
-
-            if post_per_page == 0 {
-                total_pages = 1;
-            } else {
-                // One more total posts because the first floor not included in post count.
-                total_pages = (total_post + 1).div_ceil(post_per_page);
-            }
-
-            sleep(Duration::from_millis(700)).await;
-            page += 1;
+            save_page(output_file, tid, page, content).await?;
         }
         println!("done");
         return Ok(());
@@ -58,22 +92,52 @@ pub async fn run_thread_command(args: ThreadArgs) -> Result<()> {
     println!("fetch page: tid={tid}, page={page}");
     let mut output_file = output_dir_path.clone();
     output_file.push(format!("{timestamp}_{tid}_{page}.json"));
-    download_single_page(output_file, tid, page)
+    download_single_page(&client, output_file, tid, page)
         .await
         .context("when fetching single page")?;
     println!("done");
     Ok(())
 }
 
-/// Download a single page specified by `tid` and `page` to file `output_file`.
+/// Build the client to fetch pages, retry `retry` times on failure and wait at least `delay`
+/// milliseconds between requests in each of the `concurrent` slots.
 ///
-/// Return post per page and total post as a tuple count.
-async fn download_single_page(output_file: PathBuf, tid: u32, page: u32) -> Result<(u32, u32)> {
+/// Retry delay is fixed to the default first retry delay in [ApiConfig], doubles on each retry
+/// if `retry_backoff` is set.
+fn build_client(retry: u32, retry_backoff: bool, delay: u64, concurrent: usize) -> ApiClient {
+    let default_config = ApiConfig::default();
+    let max_retry_delay = if retry_backoff {
+        default_config.max_retry_delay
+    } else {
+        default_config.retry_delay
+    };
+    ApiClient::with_config(ApiConfig {
+        max_retries: retry,
+        max_concurrent_requests: concurrent,
+        max_retry_delay,
+        ..default_config
+    })
+    .with_rate_limiter(RateLimiter::new(Duration::from_millis(delay)))
+}
+
+/// Download a single page specified by `tid` and `page` to file `output_file`.
+async fn download_single_page(
+    client: &ApiClient,
+    output_file: PathBuf,
+    tid: ThreadId,
+    page: PageNumber,
+) -> Result<()> {
     trace!("fetching content for thread {} page {}", tid, page);
 
-    let content = fetch_thread_content(tid, page)
+    let content = client
+        .fetch_thread_content(tid, page)
         .await
         .context("when running thread content")?;
+    save_page(output_file, tid, page.get(), &content).await
+}
+
+/// Save `content` of thread `tid` page `page` to file `output_file`.
+async fn save_page(output_file: PathBuf, tid: ThreadId, page: u32, content: &Thread) -> Result<()> {
     trace!(
         "saving content for thread {} page {} to {:?}",
         tid,
@@ -81,15 +145,10 @@ async fn download_single_page(output_file: PathBuf, tid: u32, page: u32) -> Resu
         output_file.as_path()
     );
 
-    let post_per_page = content.post_per_page.value().parse::<u32>().unwrap_or(1);
-    let totals = content.total_post.parse::<u32>().unwrap_or(0);
-
     fs::write(
         output_file,
-        serde_json::to_string_pretty(&content).context("when serializing thread content")?,
+        serde_json::to_string_pretty(content).context("when serializing thread content")?,
     )
     .await
-    .with_context(|| format!("when saving for thread {tid} page {page}"))?;
-
-    Ok((post_per_page, totals))
+    .with_context(|| format!("when saving for thread {tid} page {page}"))
 }
@@ -1,13 +1,39 @@
 use anyhow::{anyhow, Context, Result};
 use futures::StreamExt;
+use indicatif::{ProgressBar, ProgressStyle};
+use owo_colors::OwoColorize;
 use regex::Regex;
+use std::collections::HashSet;
+use std::fmt::Display;
 use std::future::Future;
 use std::io;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tm_api::thread::Thread as ThreadModel;
 use tokio::fs;
 
+/// Colorize text output on stdout or not.
+///
+/// Set once by [init_color] when starting.
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Colorize text output on stderr or not.
+///
+/// Set once by [init_color] when starting.
+static STDERR_COLOR_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Error that pages in thread data are not consecutive.
+#[derive(Clone, Debug, thiserror::Error)]
+#[error("thread {tid} is missing pages {missing:?}, download them first")]
+pub(crate) struct MissingPageError {
+    /// Thread id.
+    pub tid: String,
+
+    /// Page numbers not found, ascending.
+    pub missing: Vec<u32>,
+}
+
 /// Model for loading thread data from files.
 ///
 /// Each instance holds one page of post data in a thread.
@@ -79,6 +105,63 @@ where
     Ok(ret)
 }
 
+/// Build a progress bar of length `len` with message `message`.
+///
+/// The bar is drawn to stderr and hidden automatically if stderr is not a terminal.
+pub(crate) fn progress_bar(len: u64, message: impl Into<String>) -> ProgressBar {
+    let bar = ProgressBar::new(len).with_message(message.into());
+    bar.set_style(
+        ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len}")
+            .expect("invalid progress bar template")
+            .progress_chars("=> "),
+    );
+    bar
+}
+
+/// Enable colored output unless `no_color` is set or the output is not a terminal.
+///
+/// Stdout and stderr are checked separately.
+pub(crate) fn init_color(no_color: bool) {
+    COLOR_ENABLED.store(!no_color && io::stdout().is_terminal(), Ordering::Relaxed);
+    STDERR_COLOR_ENABLED.store(!no_color && io::stderr().is_terminal(), Ordering::Relaxed);
+}
+
+/// Color `text` red if colored output is enabled, for errors.
+pub(crate) fn red(text: impl Display) -> String {
+    if COLOR_ENABLED.load(Ordering::Relaxed) {
+        text.red().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Color `text` red if colored output on stderr is enabled, for errors printed to stderr.
+pub(crate) fn stderr_red(text: impl Display) -> String {
+    if STDERR_COLOR_ENABLED.load(Ordering::Relaxed) {
+        text.red().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Color `text` yellow if colored output is enabled, for warnings.
+pub(crate) fn yellow(text: impl Display) -> String {
+    if COLOR_ENABLED.load(Ordering::Relaxed) {
+        text.yellow().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Color `text` green if colored output is enabled, for success.
+pub(crate) fn green(text: impl Display) -> String {
+    if COLOR_ENABLED.load(Ordering::Relaxed) {
+        text.green().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
 /// If `path` exists, ask user to delete it.
 ///
 /// ## Returns
@@ -122,8 +205,6 @@ pub async fn load_thread_data_from_dir(path: &str) -> Result<Vec<ThreadPageData>
         .await
         .with_context(|| format!("failed to read dir {path}"))?;
 
-    let mut data = vec![];
-
     // Regex to check data file name.
     // Each data file must contain one page of thread data for a thread and the file name should be
     // in "${THREAD_ID}_${PAGE_NUMBER}.json" format so that we can parse and save thread id and page
@@ -133,8 +214,14 @@ pub async fn load_thread_data_from_dir(path: &str) -> Result<Vec<ThreadPageData>
 
     let mut tid: Option<String> = None;
 
+    let mut file_names = vec![];
     while let Some(entry) = dir.next_entry().await.context("failed to get next entry")? {
-        let file_name = entry.file_name().to_string_lossy().to_string();
+        file_names.push(entry.file_name().to_string_lossy().to_string());
+    }
+
+    // Validate file names first, all data files shall belong to the same thread.
+    let mut pages = vec![];
+    for file_name in file_names {
         let capture = match file_name_re.captures(file_name.as_str()) {
             Some(v) => v,
             None => continue,
@@ -151,18 +238,94 @@ pub async fn load_thread_data_from_dir(path: &str) -> Result<Vec<ThreadPageData>
         } else if tid.as_ref().unwrap() != cap_tid.as_str() {
             return Err(anyhow!("invalid thread data storage: the directory {} is expected to only has thread {}, but also has {}. Did you mix two or more threads in that directory?", path, tid.unwrap(), cap_tid));
         }
-        let page = capture.name("page").unwrap().as_str();
+        let page = capture.name("page").unwrap().as_str().to_string();
         let p: PathBuf = [path, file_name.as_str()].iter().collect();
-
-        let content = fs::read(p).await;
-        let thread: ThreadModel =
-            serde_json::from_slice(content?.as_slice()).context("invalid thread json data")?;
-        data.push(ThreadPageData {
-            tid: cap_tid,
-            page: page.to_string(),
-            thread,
-        });
+        pages.push((cap_tid, page, p));
     }
 
+    // Then load and deserialize them concurrently.
+    let bar = progress_bar(pages.len() as u64, format!("loading {path}"));
+    let data = parallel_future(pages.into_iter(), 8, |(tid, page, p)| {
+        let bar = bar.clone();
+        async move {
+            let content = fs::read(&p)
+                .await
+                .with_context(|| format!("failed to read thread data file {p:?}"))?;
+            let thread: ThreadModel =
+                serde_json::from_slice(content.as_slice()).context("invalid thread json data")?;
+            bar.inc(1);
+            Ok(ThreadPageData { tid, page, thread })
+        }
+    })
+    .await?;
+    bar.finish_and_clear();
+
     Ok(data)
 }
+
+/// Merge all `pages` of a thread into one [ThreadPageData], with page "all".
+///
+/// Pages are sorted by page number before merging, so posts in the merged `post_list` are in floor
+/// order. Thread info other than posts is taken from the first page.
+///
+/// # Errors
+///
+/// When `pages` is empty, or pages belong to different threads, or page number is invalid.
+pub(crate) fn merge_thread_pages(mut pages: Vec<ThreadPageData>) -> Result<ThreadPageData> {
+    let tid = match pages.first() {
+        Some(v) => v.tid.clone(),
+        None => return Err(anyhow!("no page to merge")),
+    };
+    if let Some(page) = pages.iter().find(|x| x.tid != tid) {
+        return Err(anyhow!(
+            "can not merge pages in different threads: {} and {}",
+            tid,
+            page.tid
+        ));
+    }
+
+    if let Some(page) = pages.iter().find(|x| x.page.parse::<u32>().is_err()) {
+        return Err(anyhow!("invalid page number {}", page.page));
+    }
+    sort_by_page(&mut pages);
+
+    let mut pages = pages.into_iter();
+    // Not empty, checked above.
+    let mut merged = pages.next().unwrap();
+    for page in pages {
+        merged.thread.post_list.extend(page.thread.post_list);
+    }
+    merged.page = "all".to_string();
+
+    Ok(merged)
+}
+
+/// Sort `pages` by page number, ascending.
+///
+/// Pages with invalid page number are placed at the end.
+pub(crate) fn sort_by_page(pages: &mut [ThreadPageData]) {
+    pages.sort_by_key(|x| x.page.parse::<u32>().unwrap_or(u32::MAX));
+}
+
+/// Check `pages` are consecutive pages starting from page 1, up to the max page number in
+/// `pages`.
+///
+/// `pages` do not need to be sorted.
+pub(crate) fn validate_continuity(pages: &[ThreadPageData]) -> Result<(), MissingPageError> {
+    let page_numbers = pages
+        .iter()
+        .filter_map(|x| x.page.parse::<u32>().ok())
+        .collect::<HashSet<_>>();
+    let max_page = page_numbers.iter().max().copied().unwrap_or_default();
+    let missing = (1..=max_page)
+        .filter(|x| !page_numbers.contains(x))
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(MissingPageError {
+        tid: pages.first().map(|x| x.tid.clone()).unwrap_or_default(),
+        missing,
+    })
+}
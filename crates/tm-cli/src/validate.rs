@@ -1,5 +1,6 @@
 use crate::cmd::ValidateArgs;
-use crate::config::{Config, ThreadType};
+use crate::config::{join_validation_errors, Config, ThreadType};
+use crate::utils::red;
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 use tokio::fs;
@@ -54,12 +55,19 @@ pub async fn run_validate_command(args: ValidateArgs) -> Result<()> {
                             );
                             // Revised as ok.
                             &mut passed_map
-                        } else if thread.validate_poll_format(post.body.as_str(), post.floor) {
-                            // Validate poll result.
-                            &mut passed_map
+                        } else if let Err(errors) = thread.validate_poll_format(post.body.as_str())
+                        {
+                            println!(
+                                "invalid poll: thread {} floor {}: {}",
+                                thread.name,
+                                post.floor,
+                                join_validation_errors(&errors)
+                            );
+                            // Invalid.
+                            &mut invalid_map
                         } else {
                             // Valid.
-                            &mut invalid_map
+                            &mut passed_map
                         };
 
                         match target_map.get_mut(&identifier) {
@@ -128,7 +136,7 @@ pub async fn run_validate_command(args: ValidateArgs) -> Result<()> {
 
     println!("invalid polls in each thread: ");
     for (thread, floors) in invalid_map {
-        println!("{thread}: {floors:?}");
+        println!("{}", red(format!("{thread}: {floors:?}")));
     }
 
     Ok(())
@@ -0,0 +1,23 @@
+use anyhow::Result;
+use chrono::DateTime;
+
+/// Print crate version, build time and git commit hash.
+///
+/// Build time and commit hash are recorded in build script, commit hash is "unknown" if git is not
+/// available when building.
+pub fn run_version_command() -> Result<()> {
+    let build_time = env!("TM_CLI_BUILD_TIMESTAMP")
+        .parse::<i64>()
+        .ok()
+        .and_then(|x| DateTime::from_timestamp(x, 0))
+        .map(|x| x.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("tm-cli {}", env!("CARGO_PKG_VERSION"));
+    println!("build time: {build_time}");
+    println!(
+        "commit: {}",
+        option_env!("TM_CLI_GIT_HASH").unwrap_or("unknown")
+    );
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use select::node::{Data, Node};
-use select::predicate::Name;
+use select::predicate::{Class, Name, Predicate};
 
-pub trait HtmlElementExt {
+pub trait HtmlElementExt<'a> {
     /// Check if is HTML element node.
     fn is_element(&self) -> bool;
 
@@ -11,6 +11,26 @@ pub trait HtmlElementExt {
     /// Return the text in first child if it's a text node.
     fn first_child_text(&self) -> Option<String>;
 
+    /// Return the text of all direct children that are text nodes.
+    fn all_child_texts(&self) -> Vec<String>;
+
+    /// Return all direct children that are element nodes.
+    fn all_child_elements(&self) -> Vec<Node<'a>>;
+
+    /// Return the `n`-th (0-based) direct child that is an element node.
+    fn nth_child_element(&self, n: usize) -> Option<Node<'a>>;
+
+    /// Return the text of the `n`-th (0-based) direct child that is a text node.
+    fn nth_child_text(&self, n: usize) -> Option<String>;
+
+    /// Return all classes in the `class` attribute.
+    ///
+    /// Empty if the node has no `class` attribute.
+    fn class_list(&self) -> Vec<&'a str>;
+
+    /// Check if the `class` attribute contains class `cls`.
+    fn has_class(&self, cls: &str) -> bool;
+
     /// Parsing a li type node which contains an em node and extra text in it,
     /// returns as a key value pair.
     ///
@@ -50,9 +70,40 @@ pub trait HtmlElementExt {
     /// </a>
     /// Return "1".
     fn first_end_deep_text(&self) -> String;
+
+    /// Parse the forum pagination `<div class="pg">` in or under current node.
+    ///
+    /// ```html
+    /// <div class="pg">
+    ///   <a href="...">1</a>
+    ///   <strong>2</strong>
+    ///   <a href="...">3</a>
+    ///   <a href="..." class="last">... 10</a>
+    ///   <label><span title="共 10 页"> / 10 页</span></label>
+    ///   <a href="..." class="nxt">下一页</a>
+    /// </div>
+    /// ```
+    ///
+    /// Returns `(current_page, total_pages)`, both 1-based.
+    ///
+    /// Return `None` if no pagination found, which usually means there is only one page.
+    fn parse_pagination(&self) -> Option<(usize, usize)>;
+
+    /// Find all `<a>` elements under current node, return their `(href, text)` pairs.
+    ///
+    /// Link text is trimmed. Links without `href` attribute are skipped.
+    fn extract_links(&self) -> Vec<(String, String)>;
+
+    /// Parse current `<table>` node into rows of cell texts.
+    ///
+    /// Cells are `<td>` and `<th>` in each `<tr>`, with text trimmed. Empty cells are empty
+    /// strings. Rows in nested tables are not included.
+    ///
+    /// Return `None` if current node is not a `<table>`.
+    fn parse_table(&self) -> Option<Vec<Vec<String>>>;
 }
 
-impl<'a> HtmlElementExt for Node<'a> {
+impl<'a> HtmlElementExt<'a> for Node<'a> {
     fn is_element(&self) -> bool {
         match self.data() {
             Data::Element(..) => true,
@@ -74,9 +125,44 @@ impl<'a> HtmlElementExt for Node<'a> {
         })
     }
 
+    fn all_child_texts(&self) -> Vec<String> {
+        self.children()
+            .filter_map(|x| match x.data() {
+                Data::Text(text) => Some(text.to_string()),
+                Data::Element(..) | Data::Comment(..) => None,
+            })
+            .collect()
+    }
+
+    fn all_child_elements(&self) -> Vec<Node<'a>> {
+        self.children().filter(|x| x.is_element()).collect()
+    }
+
+    fn nth_child_element(&self, n: usize) -> Option<Node<'a>> {
+        self.children().filter(|x| x.is_element()).nth(n)
+    }
+
+    fn nth_child_text(&self, n: usize) -> Option<String> {
+        self.children()
+            .filter(|x| x.is_text())
+            .nth(n)
+            .map(|x| x.text())
+    }
+
+    fn class_list(&self) -> Vec<&'a str> {
+        self.attr("class")
+            .unwrap_or("")
+            .split_whitespace()
+            .collect()
+    }
+
+    fn has_class(&self, cls: &str) -> bool {
+        self.class_list().contains(&cls)
+    }
+
     fn parse_li_em_group(&self, second: bool) -> Option<(String, String)> {
         // Check if the first child is `<em>`.
-        let key = match self.children().filter(|x| x.is_element()).next() {
+        let key = match self.all_child_elements().first() {
             Some(v) => match v.data() {
                 Data::Element(name, ..) if name.local.to_string() == "em" => {
                     match v.first_child_text() {
@@ -128,11 +214,76 @@ impl<'a> HtmlElementExt for Node<'a> {
     }
 
     fn first_end_deep_text(&self) -> String {
-        let mut ch = self.children().filter(|x| x.is_element());
-        if let Some(v) = ch.next() {
+        if let Some(v) = self.all_child_elements().first() {
             v.first_end_deep_text()
         } else {
             self.text()
         }
     }
+
+    fn parse_pagination(&self) -> Option<(usize, usize)> {
+        let pg = if self.is(Name("div")) && self.has_class("pg") {
+            *self
+        } else {
+            self.find(Name("div").and(Class("pg"))).next()?
+        };
+
+        let current = pg
+            .find(Name("strong"))
+            .next()
+            .and_then(|x| parse_number(x.text().as_str()))?;
+        // The largest number in pagination is the last page, either in the "last" link or in
+        // the page count label.
+        let total = pg
+            .descendants()
+            .filter(|x| x.is_text())
+            .filter_map(|x| parse_number(x.text().as_str()))
+            .max()
+            .unwrap_or(current)
+            .max(current);
+
+        Some((current, total))
+    }
+
+    fn extract_links(&self) -> Vec<(String, String)> {
+        self.find(Name("a"))
+            .filter_map(|x| Some((x.attr("href")?.to_string(), x.text().trim().to_string())))
+            .collect()
+    }
+
+    fn parse_table(&self) -> Option<Vec<Vec<String>>> {
+        if !self.is(Name("table")) {
+            return None;
+        }
+
+        let rows = self
+            .find(Name("tr"))
+            .filter(|row| {
+                // Only rows belong to current table, not the nested ones.
+                let mut parent = row.parent();
+                while let Some(p) = parent {
+                    if p.is(Name("table")) {
+                        return p.index() == self.index();
+                    }
+                    parent = p.parent();
+                }
+                false
+            })
+            .map(|row| {
+                row.children()
+                    .filter(|x| x.is(Name("td").or(Name("th"))))
+                    .map(|x| x.text().trim().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Some(rows)
+    }
+}
+
+/// Parse the first consecutive digits in `text` as number.
+fn parse_number(text: &str) -> Option<usize> {
+    text.split(|x: char| !x.is_ascii_digit())
+        .find(|x| !x.is_empty())
+        .and_then(|x| x.parse::<usize>().ok())
 }
@@ -1,7 +1,10 @@
 use proc_macro::TokenStream;
 
+#[cfg(feature = "regex")]
+mod regex;
 #[cfg(feature = "selector")]
 mod selector;
+mod url;
 mod utils;
 
 /// Macro to generate Scraper::Selector from static string with compile time check.
@@ -11,8 +14,86 @@ mod utils;
 ///
 /// let my_selector = selector!("div > a");
 /// ```
+///
+/// Multiple selectors separated by `,` select elements matching any of them:
+///
+/// ```
+/// use tm_macros::selector;
+///
+/// let html = scraper::Html::parse_fragment(r#"<div class="foo">1</div><span class="bar">2</span><p>3</p>"#);
+/// let my_selector = selector!("div.foo, span.bar");
+/// assert_eq!(html.select(&my_selector).count(), 2);
+/// ```
 #[cfg(feature = "selector")]
 #[proc_macro]
 pub fn selector(input: TokenStream) -> TokenStream {
     selector::selector_internal(input)
 }
+
+/// Same as [selector!], but combine multiple selectors into one, selecting elements matching any
+/// of them.
+///
+/// Each selector is checked at compile time.
+///
+/// ```
+/// use tm_macros::selector_all;
+///
+/// let html = scraper::Html::parse_fragment(r#"<div class="foo">1</div><span class="bar">2</span><p>3</p>"#);
+/// let my_selector = selector_all!("div.foo", "span.bar");
+/// assert_eq!(html.select(&my_selector).count(), 2);
+/// ```
+#[cfg(feature = "selector")]
+#[proc_macro]
+pub fn selector_all(input: TokenStream) -> TokenStream {
+    selector::selector_all_internal(input)
+}
+
+/// Macro to build url on forum, with compile time check on path and query parameter names.
+///
+/// Format: `forum_url!([base = expr,] "path", key = value, ...)`.
+///
+/// * `base` is optional, default to `tm_types::BASE_URL`. Empty `base` builds a relative url
+///   without leading `/`.
+/// * `path` is a string literal without leading `/` and query parameters.
+/// * `key` is an identifier or string literal, `value` is anything implements `ToString` and is
+///   percent-encoded with `tm_types::percent_encode`.
+///
+/// The caller crate shall depend on `tm-types`.
+///
+/// ```
+/// use tm_macros::forum_url;
+///
+/// let tid = 123;
+/// let url = forum_url!("forum.php", mod = "viewthread", tid = tid, page = 2);
+/// assert_eq!(url, format!("{}/forum.php?mod=viewthread&tid=123&page=2", tm_types::BASE_URL));
+///
+/// let base = "https://tsdm39.com";
+/// let url = forum_url!(base = base, "home.php", mod = "space", "username" = "a&b c");
+/// assert_eq!(url, "https://tsdm39.com/home.php?mod=space&username=a%26b%20c");
+///
+/// let url = forum_url!(base = base, "forum.php");
+/// assert_eq!(url, "https://tsdm39.com/forum.php");
+///
+/// let url = forum_url!(base = "", "forum.php", mod = "redirect");
+/// assert_eq!(url, "forum.php?mod=redirect");
+/// ```
+#[proc_macro]
+pub fn forum_url(input: TokenStream) -> TokenStream {
+    url::forum_url_internal(input)
+}
+
+/// Macro to get `&'static regex::Regex` from static string with compile time check.
+///
+/// The regex is built once on first use and reused after that.
+///
+/// ```
+/// use tm_macros::regex;
+///
+/// let re = regex!(r#"(?<tid>\d+)_(?<page>\d+).json"#);
+/// assert_eq!(&re.captures("123_4.json").unwrap()["page"], "4");
+/// ```
+#[cfg(feature = "regex")]
+#[proc_macro]
+pub fn regex(input: TokenStream) -> TokenStream {
+    regex::regex_internal(input)
+}
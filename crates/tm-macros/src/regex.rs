@@ -0,0 +1,20 @@
+use crate::utils::compiling_error;
+use proc_macro::TokenStream;
+use quote::quote;
+use regex::Regex;
+use syn::{parse_macro_input, LitStr};
+
+pub fn regex_internal(input: TokenStream) -> TokenStream {
+    let pattern = parse_macro_input!(input as LitStr);
+    let pattern_str = pattern.value();
+    let stream = match Regex::new(pattern_str.as_str()) {
+        Ok(_) => quote!({
+            static RE: ::std::sync::OnceLock<::regex::Regex> = ::std::sync::OnceLock::new();
+            RE.get_or_init(|| ::regex::Regex::new(#pattern_str).unwrap())
+        })
+        .into(),
+        Err(e) => compiling_error!(pattern.span(), "invalid regex: {}", e),
+    };
+
+    stream
+}
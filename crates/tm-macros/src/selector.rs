@@ -2,7 +2,8 @@ use crate::utils::compiling_error;
 use proc_macro::TokenStream;
 use quote::quote;
 use scraper::Selector;
-use syn::{parse_macro_input, LitStr};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, LitStr, Token};
 
 pub fn selector_internal(input: TokenStream) -> TokenStream {
     let selector = parse_macro_input!(input as LitStr);
@@ -21,3 +22,30 @@ pub fn selector_internal(input: TokenStream) -> TokenStream {
 
     stream
 }
+
+pub fn selector_all_internal(input: TokenStream) -> TokenStream {
+    let selectors =
+        parse_macro_input!(input with Punctuated::<LitStr, Token![,]>::parse_terminated);
+    if selectors.is_empty() {
+        return compiling_error!(
+            proc_macro2::Span::call_site(),
+            "expected at least one selector"
+        );
+    }
+
+    for selector in selectors.iter() {
+        if let Err(e) = Selector::parse(selector.value().as_str()) {
+            return compiling_error!(selector.span(), "invalid scraper selector: {}", e);
+        }
+    }
+
+    let selector_str = selectors
+        .iter()
+        .map(|x| x.value())
+        .collect::<Vec<_>>()
+        .join(", ");
+    quote!(
+        ::scraper::Selector::parse(#selector_str).unwrap()
+    )
+    .into()
+}
@@ -0,0 +1,135 @@
+use proc_macro::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Expr, Ident, LitStr, Token};
+
+/// Input of `forum_url!`: `[base = expr,] "path", key = value, ...`.
+struct ForumUrl {
+    /// Optional base url, default to `tm_types::BASE_URL`.
+    ///
+    /// Empty base means relative url.
+    base: Option<Expr>,
+
+    /// Path of the page, like `forum.php`.
+    path: LitStr,
+
+    /// Query parameters, in order.
+    params: Vec<Param>,
+}
+
+/// A single `key = value` query parameter.
+///
+/// Key is an identifier, keywords like `mod` included, or a string literal.
+struct Param {
+    key: String,
+    span: proc_macro2::Span,
+    value: Expr,
+}
+
+impl Parse for Param {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let (key, span) = if input.peek(LitStr) {
+            let lit = input.parse::<LitStr>()?;
+            (lit.value(), lit.span())
+        } else {
+            let ident = Ident::parse_any(input)?;
+            (ident.to_string(), ident.span())
+        };
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+        Ok(Self { key, span, value })
+    }
+}
+
+impl Parse for ForumUrl {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let base = if input.peek(Ident) && input.peek2(Token![=]) {
+            let ident = input.parse::<Ident>()?;
+            if ident != "base" {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "expected url path string literal or `base = expr` before it",
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            let base = input.parse()?;
+            input.parse::<Token![,]>()?;
+            Some(base)
+        } else {
+            None
+        };
+
+        let path = input.parse::<LitStr>()?;
+        let path_value = path.value();
+        if path_value.is_empty()
+            || path_value.starts_with('/')
+            || path_value.contains(['?', '&', '='])
+        {
+            return Err(syn::Error::new(
+                path.span(),
+                "invalid url path: expected non-empty path without leading '/', query parameters go to `key = value` args",
+            ));
+        }
+
+        let mut params = Vec::<Param>::new();
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            for param in Punctuated::<Param, Token![,]>::parse_terminated(input)? {
+                if param.key.is_empty()
+                    || !param
+                        .key
+                        .chars()
+                        .all(|x| x.is_ascii_alphanumeric() || x == '_')
+                {
+                    return Err(syn::Error::new(
+                        param.span,
+                        "invalid query parameter name: expected ascii letters, digits or '_'",
+                    ));
+                }
+                if params.iter().any(|x| x.key == param.key) {
+                    return Err(syn::Error::new(
+                        param.span,
+                        format!("duplicate query parameter {}", param.key),
+                    ));
+                }
+                params.push(param);
+            }
+        }
+
+        Ok(Self { base, path, params })
+    }
+}
+
+pub fn forum_url_internal(input: TokenStream) -> TokenStream {
+    let forum_url = parse_macro_input!(input as ForumUrl);
+
+    let base = match &forum_url.base {
+        Some(v) => quote_spanned! {v.span()=> ::std::string::ToString::to_string(&#v)},
+        None => quote! {::std::string::ToString::to_string(::tm_types::BASE_URL)},
+    };
+    let path = forum_url.path.value();
+    let params = forum_url.params.iter().enumerate().map(|(idx, param)| {
+        let (key, value) = (&param.key, &param.value);
+        let separator = if idx == 0 { '?' } else { '&' };
+        let value = quote_spanned! {value.span()=> ::std::string::ToString::to_string(&#value)};
+        quote_spanned! {param.span=>
+            __url.push(#separator);
+            __url.push_str(#key);
+            __url.push('=');
+            __url.push_str(::tm_types::percent_encode(#value.as_str()).as_str());
+        }
+    });
+
+    quote! {{
+        let mut __url = #base;
+        if !__url.is_empty() {
+            __url.push('/');
+        }
+        __url.push_str(#path);
+        #(#params)*
+        __url
+    }}
+    .into()
+}
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::num::{NonZeroU32, ParseIntError};
+use std::str::FromStr;
 use tracing::warn;
 
 /// Forum base url.
@@ -7,8 +10,24 @@ pub const BASE_URL: &str = "https://www.tsdm39.com";
 /// Yet another forum base url.
 pub const BASE_URL2: &str = "https://tsdm39.com";
 
+/// Percent-encode all bytes in `s` except unreserved characters in RFC 3986.
+///
+/// Used to encode query parameter values in forum urls, see `tm_macros::forum_url`.
+pub fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(format!("%{b:02X}").as_str());
+        }
+    }
+    encoded
+}
+
 /// Platforms the content publisher currently using.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Platform {
     /// Mobile web UI.
     WebMobile,
@@ -25,6 +44,24 @@ pub enum Platform {
     Ios,
 }
 
+impl Platform {
+    /// Human-readable platform name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::WebMobile => "WebMobile",
+            Platform::Unknown => "Unknown",
+            Platform::Android => "Android",
+            Platform::Ios => "iOS",
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Wrapper type for platform field in post data.
 ///
 /// Unfortunately the value can be either a number or string and may both exist in a single post
@@ -69,21 +106,135 @@ impl PlatformValue {
     }
 }
 
-/// Wrapper type for platform field in post data.
-///
-/// Same reason as [PlatformValue]. See [PlatformValue] for details.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(untagged)]
-pub enum PostPerPageValue {
-    IntValue(u32),
-    StringValue(String),
+/// Thread id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ThreadId(pub u32);
+
+impl From<u32> for ThreadId {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
 }
 
-impl PostPerPageValue {
-    pub fn value(&self) -> String {
-        match self {
-            PostPerPageValue::IntValue(v) => v.to_string(),
-            PostPerPageValue::StringValue(v) => v.clone(),
+impl FromStr for ThreadId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u32>().map(ThreadId)
+    }
+}
+
+impl fmt::Display for ThreadId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Define an id type holding string value.
+macro_rules! string_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub String);
+
+        impl $name {
+            /// The id as string slice.
+            pub fn as_str(&self) -> &str {
+                self.0.as_str()
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                Self(value.to_string())
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                self.0.as_str()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.0.as_str())
+            }
         }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+string_id!(
+    /// Post id.
+    PostId
+);
+
+string_id!(
+    /// User id.
+    UserId
+);
+
+string_id!(
+    /// Forum id.
+    ForumId
+);
+
+/// Page number, starts from 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PageNumber(NonZeroU32);
+
+impl PageNumber {
+    /// The first page.
+    pub const FIRST: PageNumber = PageNumber(NonZeroU32::MIN);
+
+    /// Build page number `n`, return `None` if `n` is zero.
+    pub fn new(n: u32) -> Option<PageNumber> {
+        NonZeroU32::new(n).map(PageNumber)
+    }
+
+    /// The page number value.
+    pub fn get(&self) -> u32 {
+        self.0.get()
+    }
+}
+
+impl From<NonZeroU32> for PageNumber {
+    fn from(value: NonZeroU32) -> Self {
+        Self(value)
+    }
+}
+
+impl FromStr for PageNumber {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<NonZeroU32>().map(PageNumber)
+    }
+}
+
+impl fmt::Display for PageNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
 }